@@ -0,0 +1,50 @@
+//! Per-frame cost of coloring every cell of a 400x300 grid by computing each
+//! scheme's color versus reading it from the palette's lookup table.
+//!
+//! Run with `cargo bench --bench colors`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use soundscape::config::ColorScheme;
+use soundscape::renderer::color::ColorPalette;
+use soundscape::simulation::gol::GameOfLife;
+
+const FRAMES: u32 = 200;
+
+/// Generations run first, so cell ages are spread out
+const SETTLE_GENERATIONS: u32 = 100;
+
+fn main() {
+    let mut sim = GameOfLife::with_seed(400, 300, 0.3, 1);
+    sim.set_audio_affects_rules(false);
+    for _ in 0..SETTLE_GENERATIONS {
+        sim.update(None);
+    }
+    let ages: Vec<u8> = (0..sim.height())
+        .flat_map(|y| (0..sim.width()).map(move |x| (x, y)))
+        .map(|(x, y)| sim.cell_age(x, y))
+        .collect();
+    
+    for scheme in [ColorScheme::Heat, ColorScheme::Rainbow] {
+        let palette = ColorPalette::new(scheme.clone());
+        
+        let start = Instant::now();
+        for _ in 0..FRAMES {
+            for &age in &ages {
+                black_box(palette.compute_cell_color(black_box(age), 255));
+            }
+        }
+        let computed = start.elapsed() / FRAMES;
+        
+        let start = Instant::now();
+        for _ in 0..FRAMES {
+            for &age in &ages {
+                black_box(palette.get_cell_color(black_box(age), 255));
+            }
+        }
+        let looked_up = start.elapsed() / FRAMES;
+        
+        println!("{:<8} computed: {:?}/frame, lookup table: {:?}/frame", format!("{:?}", scheme), computed, looked_up);
+    }
+}
@@ -0,0 +1,59 @@
+//! Per-frame cost of repainting the whole window versus only the cells that
+//! changed, on a grid that has mostly settled into still lifes and blinkers.
+//!
+//! Run with `cargo bench --bench render`.
+
+use std::time::Instant;
+
+use soundscape::config::{ColorScheme, Config};
+use soundscape::renderer::color::ColorPalette;
+use soundscape::renderer::draw::{self, CellCache};
+use soundscape::simulation::gol::GameOfLife;
+
+const FRAMES: u32 = 200;
+
+/// Generations run before measuring, so the soup has burned out
+const SETTLE_GENERATIONS: u32 = 500;
+
+fn main() {
+    let mut config = Config::default();
+    config.window.width = 1920;
+    config.window.height = 1080;
+    config.simulation.width = 480;
+    config.simulation.height = 270;
+    config.visualization.color_scheme = ColorScheme::Classic;
+    let palette = ColorPalette::new(config.visualization.color_scheme.clone());
+    
+    let mut sim = GameOfLife::with_seed(config.simulation.width, config.simulation.height, 0.3, 1);
+    sim.set_audio_affects_rules(false);
+    for _ in 0..SETTLE_GENERATIONS {
+        sim.update(None);
+    }
+    
+    // Full repaint, as with trails, supersampling or the radial spectrum
+    let start = Instant::now();
+    let mut full_writes = 0;
+    for _ in 0..FRAMES {
+        sim.update(None);
+        let (frame, _) = draw::render_frame(&config, &sim, &palette, None, None);
+        full_writes += frame.len() / 4;
+    }
+    let full_time = start.elapsed() / FRAMES;
+    
+    // Persistent frame with only the changed cells repainted
+    let layout = draw::Layout::new(&config, (sim.width(), sim.height()));
+    let mut frame = vec![0; layout.frame_size.0 * layout.frame_size.1 * 4];
+    let mut cache = CellCache::new();
+    cache.draw(&mut frame, &layout, &sim, &palette, 1.0);
+    let start = Instant::now();
+    let mut cached_writes = 0;
+    for _ in 0..FRAMES {
+        sim.update(None);
+        cache.draw(&mut frame, &layout, &sim, &palette, 1.0);
+        cached_writes += cache.pixels_written();
+    }
+    let cached_time = start.elapsed() / FRAMES;
+    
+    println!("full repaint:   {:>9} pixels/frame, {:?}/frame (including the update)", full_writes / FRAMES as usize, full_time);
+    println!("changed cells:  {:>9} pixels/frame, {:?}/frame (including the update)", cached_writes / FRAMES as usize, cached_time);
+}
@@ -0,0 +1,55 @@
+//! Render the first generations of an acorn to PNG files, with no window or timing
+//!
+//! Usage: cargo run --example dump_generations [output dir] [generations]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use soundscape::audio::AudioAnalyzer;
+use soundscape::config::{Config, InitialState};
+use soundscape::renderer::color::ColorPalette;
+use soundscape::renderer::draw;
+use soundscape::simulation::gol::GameOfLife;
+
+/// Synthetic audio time between generations, in seconds
+const STEP_SECS: f32 = 1.0 / 30.0;
+
+fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let out_dir = PathBuf::from(args.next().unwrap_or_else(|| "generations".to_string()));
+    let count: usize = match args.next() {
+        Some(count) => count.parse().context("Generation count must be a number")?,
+        None => 100,
+    };
+    
+    let mut config = Config::default();
+    config.simulation.width = 160;
+    config.simulation.height = 120;
+    config.simulation.initial_state = InitialState::Acorn;
+    config.window.width = 640;
+    config.window.height = 480;
+    
+    let mut sim = GameOfLife::from_config(&config.simulation, 0);
+    // Snapshots are drawn through a second grid with the same rules
+    let mut view = GameOfLife::from_config(&config.simulation, 0);
+    let analyzer = AudioAnalyzer::new(config.audio.clone());
+    let palette = ColorPalette::new(config.visualization.color_scheme.clone());
+    let frames = (1..).map(|i| analyzer.generate_test_frame(i as f32 * STEP_SECS));
+    
+    fs::create_dir_all(&out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+    for snapshot in sim.generations(frames).take(count) {
+        view.restore(&snapshot)?;
+        let (rgba, layout) = draw::render_frame(&config, &view, &palette, None, None);
+        let (width, height) = layout.frame_size;
+        let image = image::RgbaImage::from_raw(width as u32, height as u32, rgba)
+            .context("Frame buffer doesn't match its size")?;
+        let path = out_dir.join(format!("generation_{:04}.png", snapshot.generation));
+        image.save(&path).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    
+    println!("Wrote {} generations to {}", count, out_dir.display());
+    Ok(())
+}
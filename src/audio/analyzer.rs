@@ -1,10 +1,28 @@
 use anyhow::Result;
-use crossbeam_channel::Sender;
+use crossbeam_channel::{unbounded, Receiver, Sender, TrySendError};
+use log::{debug, info};
 use rustfft::{Fft, FftPlanner};
-use std::sync::{Arc, Mutex};
-use std::num::Complex;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use rustfft::num_complex::Complex;
 
-use crate::config::Config;
+use crate::audio::beat::BeatDetector;
+use crate::audio::dump::AudioDump;
+use crate::audio::resample::{Decimator, LinearResampler};
+use crate::config::{AnalysisMode, AudioConfig};
+
+/// Half-width of the noise gate's soft knee, as a fraction of the threshold
+const GATE_KNEE: f32 = 0.5;
+
+/// How much louder a new spectral peak must be than the held one to jump to it
+const PEAK_SWITCH_MARGIN: f32 = 1.5;
+
+/// Fraction of the way the tracked peak moves toward a weaker peak each frame
+const PEAK_SLEW: f32 = 0.15;
+
+/// Per-frame decay of the held peak magnitude, so fading notes give way
+const PEAK_HOLD_DECAY: f32 = 0.9;
 
 /// Represents an analyzed audio frame with frequency band information
 #[derive(Debug, Clone)]
@@ -14,60 +32,584 @@ pub struct AudioFrame {
     pub treble_energy: f32, // Energy in treble frequencies
     pub peak_frequency: f32, // Most prominent frequency
     pub overall_energy: f32, // Overall audio energy
+    pub left: Option<ChannelBands>,  // Left channel bands (stereo analysis only)
+    pub right: Option<ChannelBands>, // Right channel bands (stereo analysis only)
+    pub beat: bool,          // Bass onset detected in this frame
+    pub treble_onset: bool,  // Treble onset (e.g. a hi-hat) detected in this frame
+    pub estimated_bpm: Option<f32>, // Tempo from recent beats, once enough have been heard
+    pub energy: EnergyStats,        // Running averages of overall_energy
+    pub time: f32,                  // Seconds into the stream at the end of the analyzed window
+    pub spectrum: Option<Arc<Spectrum>>, // Full magnitude spectrum, only on a frame asked for with CaptureSpectrum
+    pub sequence: u64,              // Counts up from 1 with each frame an analyzer emits (0 for frames built directly)
+}
+
+/// Magnitudes of the FFT bins below Nyquist for one analyzed window
+#[derive(Debug, Clone)]
+pub struct Spectrum {
+    pub bin_width: f32,      // Hz between consecutive bins
+    pub magnitudes: Vec<f32>, // Windowed FFT magnitude of each bin, starting at DC
+}
+
+impl Spectrum {
+    /// One `bin,frequency_hz,magnitude` row per bin, under a header
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("bin,frequency_hz,magnitude\n");
+        for (bin, magnitude) in self.magnitudes.iter().enumerate() {
+            csv.push_str(&format!("{},{},{}\n", bin, bin as f32 * self.bin_width, magnitude));
+        }
+        csv
+    }
+}
+
+/// Running averages of the overall energy, computed once by the analyzer so
+/// every consumer sees the same values
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnergyStats {
+    pub smoothed: f32, // Follows the music over `energy_smoothing` seconds
+    pub baseline: f32, // Follows it over `energy_baseline` seconds, for spotting drops and builds
+}
+
+impl EnergyStats {
+    /// Fold in the next frame's energy, `elapsed` seconds after the last
+    fn update(&mut self, energy: f32, elapsed: f32, smoothing: f32, baseline: f32) {
+        let follow = |average: &mut f32, secs: f32| {
+            *average += (energy - *average) * (1.0 - (-elapsed / secs.max(0.001)).exp());
+        };
+        follow(&mut self.smoothed, smoothing);
+        follow(&mut self.baseline, baseline);
+    }
+}
+
+/// Band energies measured on a single channel
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelBands {
+    pub bass_energy: f32,
+    pub mid_energy: f32,
+    pub treble_energy: f32,
+    pub overall_energy: f32,
+}
+
+impl AudioFrame {
+    /// Combine separately analyzed left and right windows into one frame
+    fn from_channels(left: AudioFrame, right: AudioFrame) -> Self {
+        let louder = if left.overall_energy >= right.overall_energy { &left } else { &right };
+        
+        AudioFrame {
+            bass_energy: (left.bass_energy + right.bass_energy) / 2.0,
+            mid_energy: (left.mid_energy + right.mid_energy) / 2.0,
+            treble_energy: (left.treble_energy + right.treble_energy) / 2.0,
+            peak_frequency: louder.peak_frequency,
+            overall_energy: (left.overall_energy + right.overall_energy) / 2.0,
+            left: Some(left.bands()),
+            right: Some(right.bands()),
+            beat: false,
+            treble_onset: false,
+            estimated_bpm: None,
+            energy: EnergyStats::default(),
+            time: left.time,
+            spectrum: louder.spectrum.clone(),
+            sequence: left.sequence,
+        }
+    }
+    
+    fn bands(&self) -> ChannelBands {
+        ChannelBands {
+            bass_energy: self.bass_energy,
+            mid_energy: self.mid_energy,
+            treble_energy: self.treble_energy,
+            overall_energy: self.overall_energy,
+        }
+    }
+    
+    /// A copy of this frame with the combined bands replaced by one channel's
+    pub fn with_bands(&self, bands: &ChannelBands) -> AudioFrame {
+        AudioFrame {
+            bass_energy: bands.bass_energy,
+            mid_energy: bands.mid_energy,
+            treble_energy: bands.treble_energy,
+            peak_frequency: self.peak_frequency,
+            overall_energy: bands.overall_energy,
+            left: None,
+            right: None,
+            beat: self.beat,
+            treble_onset: self.treble_onset,
+            estimated_bpm: self.estimated_bpm,
+            energy: self.energy,
+            time: self.time,
+            spectrum: self.spectrum.clone(),
+            sequence: self.sequence,
+        }
+    }
+    
+    /// Stereo balance from -1.0 (all left) to 1.0 (all right), or None
+    /// without stereo analysis or during silence
+    pub fn balance(&self) -> Option<f32> {
+        let (left, right) = (self.left?.overall_energy, self.right?.overall_energy);
+        if left + right <= f32::EPSILON {
+            return None;
+        }
+        Some(((right - left) / (right + left)).clamp(-1.0, 1.0))
+    }
+    
+    /// A copy of this frame with every energy passed through the reactivity curve
+    pub fn with_reactivity(&self, reactivity: f32) -> AudioFrame {
+        let bands = self.bands().with_reactivity(reactivity);
+        AudioFrame {
+            left: self.left.map(|left| left.with_reactivity(reactivity)),
+            right: self.right.map(|right| right.with_reactivity(reactivity)),
+            energy: EnergyStats {
+                smoothed: shape_energy(self.energy.smoothed, reactivity),
+                baseline: shape_energy(self.energy.baseline, reactivity),
+            },
+            ..self.with_bands(&bands)
+        }
+    }
+}
+
+impl ChannelBands {
+    fn with_reactivity(&self, reactivity: f32) -> ChannelBands {
+        ChannelBands {
+            bass_energy: shape_energy(self.bass_energy, reactivity),
+            mid_energy: shape_energy(self.mid_energy, reactivity),
+            treble_energy: shape_energy(self.treble_energy, reactivity),
+            overall_energy: shape_energy(self.overall_energy, reactivity),
+        }
+    }
+}
+
+/// Steadies the reported peak frequency: it follows a clearly louder peak at
+/// once, but only glides toward peaks of similar strength, so near-equal bins
+/// trading places don't make the hue strobe
+#[derive(Debug, Clone, Copy, Default)]
+struct PeakTracker {
+    frequency: f32,
+    magnitude: f32, // Strength of the peak being followed, decaying over time
+}
+
+impl PeakTracker {
+    fn track(&mut self, frequency: f32, magnitude: f32) -> f32 {
+        self.magnitude *= PEAK_HOLD_DECAY;
+        if self.frequency <= 0.0 || magnitude > self.magnitude * PEAK_SWITCH_MARGIN {
+            self.frequency = frequency;
+        } else if frequency > 0.0 {
+            // Glide in log frequency so the pace is the same in every octave
+            self.frequency *= (frequency / self.frequency).powf(PEAK_SLEW);
+        }
+        self.magnitude = self.magnitude.max(magnitude);
+        self.frequency
+    }
+}
+
+/// Gamma curve over the normalized range: reactivity above 1 lifts quiet
+/// energies toward full strength, below 1 holds them back
+fn shape_energy(energy: f32, reactivity: f32) -> f32 {
+    if energy > 0.0 && energy < 1.0 {
+        energy.powf(1.0 / reactivity.max(0.01))
+    } else {
+        energy
+    }
+}
+
+/// Send `item` without blocking; when the channel is full, drop its oldest
+/// item through `receiver` and try once more, so the newest data gets through
+pub fn send_latest<T>(sender: &Sender<T>, receiver: &Receiver<T>, item: T) {
+    if let Err(TrySendError::Full(item)) = sender.try_send(item) {
+        if receiver.try_recv().is_ok() {
+            debug!("Listener is falling behind; dropped its oldest item");
+        }
+        if let Err(TrySendError::Full(_)) = sender.try_send(item) {
+            debug!("Listener is falling behind; dropped the newest item");
+        }
+    }
+}
+
+/// A channel the analyzer pushes frames into
+struct FrameSink {
+    sender: Sender<AudioFrame>,
+    oldest: Option<Receiver<AudioFrame>>, // Drops the oldest frame when full, instead of the new one
+}
+
+impl FrameSink {
+    fn send(&self, frame: AudioFrame) {
+        match &self.oldest {
+            Some(receiver) => send_latest(&self.sender, receiver, frame),
+            None => {
+                if let Err(TrySendError::Full(frame)) = self.sender.try_send(frame) {
+                    debug!("Listener is falling behind; dropped audio frame {}", frame.sequence);
+                }
+            },
+        }
+    }
+}
+
+/// Runtime reconfiguration requests for a running analyzer
+#[derive(Debug, Clone)]
+pub enum AnalyzerCommand {
+    SetMode(AnalysisMode),
+    SetFftSize(usize),
+    SetPaused(bool), // Follow the playback transport
+    CaptureSpectrum, // Attach the full spectrum to the next frame
 }
 
 /// Analyzes audio data using FFT to extract frequency information
 pub struct AudioAnalyzer {
-    config: Arc<Config>,
+    config: AudioConfig,
+    planner: FftPlanner<f32>, // Keeps every planned size, so switching back to one is free
     fft: Arc<dyn Fft<f32>>,
-    sender: Sender<AudioFrame>,
+    senders: Vec<FrameSink>,  // Where frames are pushed, if anyone is listening
     buffer: Vec<Complex<f32>>,
     scratch: Vec<Complex<f32>>,
+    pending: [Vec<f32>; 2], // Samples carried over until a full FFT window is available (mono/left, right)
+    mode: AnalysisMode,
+    input_rate: u32,                   // Sample rate of incoming audio
+    decimators: [Decimator; 2],        // Optional anti-aliased downsampling of high input rates
+    resamplers: [LinearResampler; 2],  // Convert the (decimated) input rate to the configured rate
+    beat_detector: BeatDetector,
+    treble_detector: BeatDetector,     // Flags onsets in the treble band the way beats are in the bass
+    peak_tracker: PeakTracker,
+    energy_stats: EnergyStats,
+    stream_time: f64,                  // Seconds into the stream at the newest pending sample
+    sequence: u64,                     // Sequence number of the last emitted frame
+    paused: bool,                      // Input is dropped and no frames are produced
+    capture_spectrum: bool,            // The next frame carries its full spectrum
+    dump: Option<AudioDump>,           // Records the analyzed signal, if requested
+    commands: Receiver<AnalyzerCommand>,
+    command_sender: Sender<AnalyzerCommand>,
 }
 
 impl AudioAnalyzer {
-    pub fn new(config: Arc<Config>, sender: Sender<AudioFrame>) -> Self {
-        let fft_size = config.audio.fft_size;
+    /// Create a standalone analyzer; frames are only returned from `process_audio`
+    pub fn new(config: AudioConfig) -> Self {
+        let fft_size = config.fft_size;
         let mut planner = FftPlanner::new();
         let fft = planner.plan_fft_forward(fft_size);
         let buffer = vec![Complex::new(0.0, 0.0); fft_size];
-        let scratch = vec![Complex::new(0.0, 0.0); fft_size];
+        let scratch = vec![Complex::new(0.0, 0.0); fft.get_inplace_scratch_len()];
+        let mode = config.analysis_mode;
+        let input_rate = config.sample_rate;
+        let decimation = decimation_factor(&config, input_rate);
+        let decimators = [Decimator::new(decimation), Decimator::new(decimation)];
+        let resamplers = [
+            LinearResampler::new(input_rate / decimation, config.sample_rate),
+            LinearResampler::new(input_rate / decimation, config.sample_rate),
+        ];
+        let (command_sender, commands) = unbounded();
+        let frames_per_second = config.sample_rate as f32 / hop_size(&config) as f32;
         
         AudioAnalyzer {
             config,
+            planner,
             fft,
-            sender,
+            senders: Vec::new(),
             buffer,
             scratch,
+            pending: [Vec::with_capacity(fft_size * 2), Vec::with_capacity(fft_size * 2)],
+            mode,
+            input_rate,
+            decimators,
+            resamplers,
+            beat_detector: BeatDetector::new(frames_per_second),
+            treble_detector: BeatDetector::new(frames_per_second),
+            peak_tracker: PeakTracker::default(),
+            energy_stats: EnergyStats::default(),
+            stream_time: 0.0,
+            sequence: 0,
+            paused: false,
+            capture_spectrum: false,
+            dump: None,
+            commands,
+            command_sender,
+        }
+    }
+    
+    /// Create an analyzer that also pushes every frame it produces to `sender`
+    pub fn with_sender(config: AudioConfig, sender: Sender<AudioFrame>) -> Self {
+        let mut analyzer = Self::new(config);
+        analyzer.add_sender(sender);
+        analyzer
+    }
+    
+    /// Also push every frame to `sender`; full channels drop frames rather than block
+    pub fn add_sender(&mut self, sender: Sender<AudioFrame>) {
+        self.senders.push(FrameSink { sender, oldest: None });
+    }
+    
+    /// Push every frame to `sender`, making room in a full channel by dropping
+    /// its oldest frame through `receiver` (a clone of the reading end)
+    ///
+    /// A reader that stalls for a while then resumes with the latest frames
+    /// instead of ones queued before the stall.
+    pub fn add_latest_sender(&mut self, sender: Sender<AudioFrame>, receiver: Receiver<AudioFrame>) {
+        self.senders.push(FrameSink { sender, oldest: Some(receiver) });
+    }
+    
+    /// Record every sample that reaches the FFT, after downmixing and resampling
+    pub fn set_dump(&mut self, dump: AudioDump) {
+        self.dump = Some(dump);
+    }
+    
+    /// Handle for reconfiguring the analyzer after it has moved to its thread
+    pub fn command_sender(&self) -> Sender<AnalyzerCommand> {
+        self.command_sender.clone()
+    }
+    
+    /// Switch between mono mixdown and per-channel analysis
+    pub fn set_mode(&mut self, mode: AnalysisMode) {
+        if self.mode != mode {
+            // Buffered samples belong to the old layout; start the next window fresh
+            for pending in self.pending.iter_mut() {
+                pending.clear();
+            }
+            self.reset_resamplers();
+            self.mode = mode;
+            info!("Analysis mode: {:?}", mode);
+        }
+    }
+    
+    /// Change the FFT window size, reusing the plan if this size was used before
+    ///
+    /// Buffered samples are dropped since they were gathered for the old
+    /// window, and the beat detector restarts when the frame rate follows the
+    /// window size (no `analysis_rate`).
+    pub fn set_fft_size(&mut self, fft_size: usize) {
+        if fft_size == 0 || fft_size == self.config.fft_size {
+            return;
+        }
+        self.config.fft_size = fft_size;
+        info!("FFT size: {}", fft_size);
+        self.fft = self.planner.plan_fft_forward(fft_size);
+        self.buffer.resize(fft_size, Complex::new(0.0, 0.0));
+        self.scratch.resize(self.fft.get_inplace_scratch_len(), Complex::new(0.0, 0.0));
+        for pending in self.pending.iter_mut() {
+            pending.clear();
+        }
+        if self.config.analysis_rate.is_none() {
+            let frames_per_second = self.config.sample_rate as f32 / hop_size(&self.config) as f32;
+            self.beat_detector = BeatDetector::new(frames_per_second);
+            self.treble_detector = BeatDetector::new(frames_per_second);
+        }
+    }
+    
+    /// Stop or restart producing frames, e.g. while playback is paused
+    ///
+    /// Samples fed in while paused are dropped. On resume the samples buffered
+    /// before the pause are discarded too, so the first frame after it is
+    /// analyzed entirely from audio played since.
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.paused && !paused {
+            for pending in self.pending.iter_mut() {
+                pending.clear();
+            }
+            self.reset_resamplers();
+        }
+        if self.paused != paused {
+            debug!("Analysis {}", if paused { "paused" } else { "resumed" });
+        }
+        self.paused = paused;
+    }
+    
+    /// Whether `set_paused` has stopped the analyzer
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    
+    /// Attach the magnitude spectrum to the next frame produced, once
+    ///
+    /// In stereo mode the spectrum is the louder channel's.
+    pub fn capture_spectrum(&mut self) {
+        self.capture_spectrum = true;
+    }
+    
+    /// Apply reconfiguration requests sent through `command_sender`
+    fn apply_commands(&mut self) {
+        while let Ok(command) = self.commands.try_recv() {
+            match command {
+                AnalyzerCommand::SetMode(mode) => self.set_mode(mode),
+                AnalyzerCommand::SetFftSize(fft_size) => self.set_fft_size(fft_size),
+                AnalyzerCommand::SetPaused(paused) => self.set_paused(paused),
+                AnalyzerCommand::CaptureSpectrum => self.capture_spectrum(),
+            }
+        }
+    }
+    
+    /// Declare where in the stream the next samples start, in seconds
+    ///
+    /// Frames are stamped with their position from the samples counted since,
+    /// so a source that may drop samples (as the playback tap does when
+    /// analysis falls behind) should call this before every block.
+    pub fn set_stream_time(&mut self, seconds: f64) {
+        self.stream_time = seconds;
+    }
+    
+    /// Declare the sample rate of the audio being fed in
+    ///
+    /// Input at any other rate than `AudioConfig::sample_rate` is resampled to
+    /// it so the band-to-bin mapping stays correct.
+    pub fn set_input_sample_rate(&mut self, rate: u32) {
+        if self.input_rate != rate {
+            self.input_rate = rate;
+            self.reset_resamplers();
+        }
+    }
+    
+    /// Restart the input stage (decimation and resampling) for the current input rate
+    fn reset_resamplers(&mut self) {
+        let decimation = decimation_factor(&self.config, self.input_rate);
+        for decimator in self.decimators.iter_mut() {
+            *decimator = Decimator::new(decimation);
+        }
+        for resampler in self.resamplers.iter_mut() {
+            *resampler = LinearResampler::new(self.input_rate / decimation, self.config.sample_rate);
         }
     }
 
-    /// Process a raw audio buffer and extract frequency information
-    pub fn process_audio(&mut self, samples: &[f32]) -> Result<AudioFrame> {
-        let fft_size = self.config.audio.fft_size;
-        let sample_rate = self.config.audio.sample_rate as f32;
+    /// Process a raw mono audio buffer and extract frequency information
+    ///
+    /// Samples are accumulated across calls and a frame is only analyzed once a
+    /// full `fft_size` window is available, with the remainder carried forward.
+    /// Frames are emitted every `analysis_rate` hop (overlapping windows when
+    /// the hop is shorter than `fft_size`), or once per window by default.
+    /// Short decoded chunks (track start/end) therefore never get zero-padded
+    /// into artificially quiet frames. Returns the last frame emitted, if any.
+    pub fn process_audio(&mut self, samples: &[f32]) -> Result<Option<AudioFrame>> {
+        self.process_interleaved(samples, 1)
+    }
+    
+    /// Process interleaved audio, mixing it down or splitting it by channel
+    /// depending on the current analysis mode
+    pub fn process_interleaved(&mut self, samples: &[f32], channels: u16) -> Result<Option<AudioFrame>> {
+        self.apply_commands();
+        if self.paused {
+            return Ok(None);
+        }
+        
+        let channels = channels.max(1) as usize;
+        let mut split: [Vec<f32>; 2] = [Vec::new(), Vec::new()];
+        match self.mode {
+            AnalysisMode::Mono => {
+                for frame in samples.chunks_exact(channels) {
+                    split[0].push(frame.iter().sum::<f32>() / channels as f32);
+                }
+            },
+            AnalysisMode::Stereo => {
+                // Surround channels fold into the side they play from, and
+                // mono sources feed the same signal to both sides
+                let weights = stereo_weights(channels);
+                for frame in samples.chunks_exact(channels) {
+                    let (mut left, mut right) = (0.0, 0.0);
+                    for (sample, (to_left, to_right)) in frame.iter().zip(&weights) {
+                        left += sample * to_left;
+                        right += sample * to_right;
+                    }
+                    split[0].push(left);
+                    split[1].push(right);
+                }
+            },
+        }
+        
+        self.stream_time += split[0].len() as f64 / self.input_rate.max(1) as f64;
+        let decimation = decimation_factor(&self.config, self.input_rate);
+        if decimation > 1 {
+            for (channel, samples) in split.iter_mut().enumerate() {
+                let mut decimated = Vec::with_capacity(samples.len() / decimation as usize + 1);
+                self.decimators[channel].process(samples, &mut decimated);
+                *samples = decimated;
+            }
+        }
+        let buffered = [self.pending[0].len(), self.pending[1].len()];
+        for (channel, samples) in split.iter().enumerate() {
+            if self.input_rate / decimation == self.config.sample_rate {
+                self.pending[channel].extend_from_slice(samples);
+            } else {
+                self.resamplers[channel].process(samples, &mut self.pending[channel]);
+            }
+        }
+        if let Some(dump) = self.dump.as_mut() {
+            let channels = match self.mode {
+                AnalysisMode::Mono => 1,
+                AnalysisMode::Stereo => 2,
+            };
+            let added: Vec<&[f32]> = (0..channels)
+                .map(|channel| &self.pending[channel][buffered[channel]..])
+                .collect();
+            dump.write(&added)?;
+        }
+        
+        // Each frame analyzes the fft_size samples ending one hop in (or at the
+        // window end when hops overlap), then advances by one hop
+        let fft_size = self.config.fft_size;
+        let hop = hop_size(&self.config);
+        let window_end = hop.max(fft_size);
+        let mut latest = None;
+        while self.pending[0].len() >= window_end {
+            let start = window_end - fft_size;
+            let (mut frame, peak_magnitude) = match self.mode {
+                AnalysisMode::Mono => self.analyze_window(0, start),
+                AnalysisMode::Stereo => {
+                    let (left, left_peak) = self.analyze_window(0, start);
+                    let (right, right_peak) = self.analyze_window(1, start);
+                    let peak = if left.overall_energy >= right.overall_energy { left_peak } else { right_peak };
+                    (AudioFrame::from_channels(left, right), peak)
+                },
+            };
+            frame.peak_frequency = self.peak_tracker.track(frame.peak_frequency, peak_magnitude);
+            frame.beat = self.beat_detector.detect(frame.bass_energy);
+            frame.estimated_bpm = self.beat_detector.bpm();
+            frame.treble_onset = self.treble_detector.detect(frame.treble_energy);
+            let hop_secs = hop as f32 / self.config.sample_rate as f32;
+            self.energy_stats.update(
+                frame.overall_energy,
+                hop_secs,
+                self.config.energy_smoothing,
+                self.config.energy_baseline,
+            );
+            frame.energy = self.energy_stats;
+            let unanalyzed = (self.pending[0].len() - window_end) as f64 / self.config.sample_rate as f64;
+            frame.time = (self.stream_time - unanalyzed) as f32;
+            self.sequence += 1;
+            frame.sequence = self.sequence;
+            self.capture_spectrum = false;
+            for pending in self.pending.iter_mut() {
+                let consumed = hop.min(pending.len());
+                pending.drain(..consumed);
+            }
+            
+            // Send the frame to the visualization thread (and any other listeners)
+            for sink in &self.senders {
+                sink.send(frame.clone());
+            }
+            latest = Some(frame);
+        }
+        
+        Ok(latest)
+    }
+    
+    /// Run the FFT over `fft_size` pending samples of one buffer, starting at
+    /// `start`, returning the frame and the magnitude of its peak bin
+    fn analyze_window(&mut self, channel: usize, start: usize) -> (AudioFrame, f32) {
+        let fft_size = self.config.fft_size;
+        let sample_rate = self.config.sample_rate as f32;
         
         // Prepare input buffer (apply window function and convert to complex)
-        for i in 0..fft_size.min(samples.len()) {
+        for i in 0..fft_size {
             // Apply a simple Hann window function
             let window = 0.5 * (1.0 - (2.0 * std::f32::consts::PI * i as f32 / fft_size as f32).cos());
-            self.buffer[i] = Complex::new(samples[i] * window, 0.0);
-        }
-        
-        // Zero-pad if needed
-        for i in samples.len()..fft_size {
-            self.buffer[i] = Complex::new(0.0, 0.0);
+            self.buffer[i] = Complex::new(self.pending[channel][start + i] * window, 0.0);
         }
         
         // Perform FFT
         self.fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
         
         // Analyze frequency bands
-        let bass_range = self.config.audio.bass_range;
-        let mid_range = self.config.audio.mid_range;
-        let treble_range = self.config.audio.treble_range;
+        let bass_range = self.config.bass_range;
+        let mid_range = self.config.mid_range;
+        let treble_range = self.config.treble_range;
         
         let bin_width = sample_rate / fft_size as f32;
+        let spectrum = self.capture_spectrum.then(|| {
+            let magnitudes = self.buffer[..fft_size / 2].iter().map(|bin| bin.norm()).collect();
+            Arc::new(Spectrum { bin_width, magnitudes })
+        });
         
         let bass_start = (bass_range.0 / bin_width) as usize;
         let bass_end = (bass_range.1 / bin_width) as usize;
@@ -78,10 +620,10 @@ impl AudioAnalyzer {
         let treble_start = (treble_range.0 / bin_width) as usize;
         let treble_end = (treble_range.1 / bin_width) as usize;
         
-        // Calculate energy in each band
-        let bass_energy = self.calculate_band_energy(bass_start, bass_end);
-        let mid_energy = self.calculate_band_energy(mid_start, mid_end);
-        let treble_energy = self.calculate_band_energy(treble_start, treble_end);
+        // Calculate energy in each band, balanced by the per-band sensitivities
+        let bass_energy = self.calculate_band_energy(bass_start, bass_end) * self.config.bass_sensitivity;
+        let mid_energy = self.calculate_band_energy(mid_start, mid_end) * self.config.mid_sensitivity;
+        let treble_energy = self.calculate_band_energy(treble_start, treble_end) * self.config.treble_sensitivity;
         
         // Find peak frequency
         let mut max_magnitude = 0.0;
@@ -95,23 +637,51 @@ impl AudioAnalyzer {
             }
         }
         
-        let peak_frequency = peak_bin as f32 * bin_width;
-        let overall_energy = bass_energy + mid_energy + treble_energy;
+        // Fit a parabola through the peak and its neighbors for sub-bin accuracy
+        let mut peak_offset = 0.0;
+        if peak_bin > 1 && peak_bin + 1 < fft_size / 2 {
+            let before = self.buffer[peak_bin - 1].norm();
+            let after = self.buffer[peak_bin + 1].norm();
+            let curvature = before - 2.0 * max_magnitude + after;
+            if curvature.abs() > f32::EPSILON {
+                peak_offset = (0.5 * (before - after) / curvature).clamp(-0.5, 0.5);
+            }
+        }
+        let peak_frequency = (peak_bin as f32 + peak_offset) * bin_width;
         
-        // Apply sensitivity adjustment
-        let sensitivity = self.config.audio.sensitivity;
+        // Apply the master sensitivity on top, then gate out the noise floor
+        let sensitivity = self.config.sensitivity;
+        let threshold = self.config.gate_threshold;
+        let bass_energy = noise_gate(bass_energy * sensitivity, threshold);
+        let mid_energy = noise_gate(mid_energy * sensitivity, threshold);
+        let treble_energy = noise_gate(treble_energy * sensitivity, threshold);
+        let (bass_energy, mid_energy, treble_energy) = if self.config.log_amplitude {
+            let (floor, range) = (self.config.floor_db, self.config.range_db);
+            (
+                log_amplitude(bass_energy, floor, range),
+                log_amplitude(mid_energy, floor, range),
+                log_amplitude(treble_energy, floor, range),
+            )
+        } else {
+            (bass_energy, mid_energy, treble_energy)
+        };
         let frame = AudioFrame {
-            bass_energy: bass_energy * sensitivity,
-            mid_energy: mid_energy * sensitivity,
-            treble_energy: treble_energy * sensitivity,
+            bass_energy,
+            mid_energy,
+            treble_energy,
             peak_frequency,
-            overall_energy: overall_energy * sensitivity,
+            overall_energy: bass_energy + mid_energy + treble_energy,
+            left: None,
+            right: None,
+            beat: false,
+            treble_onset: false,
+            estimated_bpm: None,
+            energy: EnergyStats::default(),
+            time: 0.0,
+            spectrum,
+            sequence: 0,
         };
-        
-        // Send the frame to the visualization thread
-        let _ = self.sender.try_send(frame.clone());
-        
-        Ok(frame)
+        (frame, max_magnitude)
     }
     
     fn calculate_band_energy(&self, start_bin: usize, end_bin: usize) -> f32 {
@@ -137,20 +707,136 @@ impl AudioAnalyzer {
     }
 }
 
+/// Zero energies below `threshold`, easing them back in over a soft knee
+fn noise_gate(energy: f32, threshold: f32) -> f32 {
+    let knee = threshold * GATE_KNEE;
+    if energy >= threshold + knee {
+        energy
+    } else if energy <= threshold - knee {
+        0.0
+    } else {
+        let t = (energy - (threshold - knee)) / (2.0 * knee);
+        energy * t * t
+    }
+}
+
+/// Share of each channel in the left and right analysis inputs, for the
+/// standard WAVE channel orders (L R C LFE, then rear and side pairs)
+///
+/// Each side's shares add up to 1, so stereo and surround files measure at
+/// the same level. Unknown layouts alternate between left and right.
+fn stereo_weights(channels: usize) -> Vec<(f32, f32)> {
+    const LEFT: (f32, f32) = (1.0, 0.0);
+    const RIGHT: (f32, f32) = (0.0, 1.0);
+    const CENTER: (f32, f32) = (std::f32::consts::FRAC_1_SQRT_2, std::f32::consts::FRAC_1_SQRT_2);
+    let layout = match channels {
+        1 => vec![(1.0, 1.0)],
+        3 => vec![LEFT, RIGHT, CENTER],
+        5 => vec![LEFT, RIGHT, CENTER, LEFT, RIGHT],
+        6 => vec![LEFT, RIGHT, CENTER, CENTER, LEFT, RIGHT],
+        8 => vec![LEFT, RIGHT, CENTER, CENTER, LEFT, RIGHT, LEFT, RIGHT],
+        _ => (0..channels).map(|channel| if channel % 2 == 0 { LEFT } else { RIGHT }).collect(),
+    };
+    let left_total: f32 = layout.iter().map(|weights| weights.0).sum();
+    let right_total: f32 = layout.iter().map(|weights| weights.1).sum();
+    layout.iter()
+        .map(|&(left, right)| (left / left_total, right / right_total))
+        .collect()
+}
+
+/// Map an energy onto 0-1 by its level in dB, from `floor_db` up to
+/// `floor_db + range_db`; silence stays at 0
+fn log_amplitude(energy: f32, floor_db: f32, range_db: f32) -> f32 {
+    if energy <= 0.0 {
+        return 0.0;
+    }
+    ((20.0 * energy.log10() - floor_db) / range_db.max(f32::EPSILON)).clamp(0.0, 1.0)
+}
+
+/// Samples between consecutive frames: `analysis_rate` if set, else one window
+fn hop_size(config: &AudioConfig) -> usize {
+    match config.analysis_rate {
+        Some(rate) if rate > 0.0 => (config.sample_rate as f32 / rate).round().max(1.0) as usize,
+        _ => config.fft_size,
+    }
+}
+
+/// Decimation applied to input at `input_rate`: the configured factor, or 1
+/// when that would take the input below the analysis `sample_rate`
+fn decimation_factor(config: &AudioConfig, input_rate: u32) -> u32 {
+    match config.decimation {
+        Some(factor) if factor > 1 && input_rate / factor >= config.sample_rate => factor,
+        _ => 1,
+    }
+}
+
 // For testing/development without real audio input
 impl AudioAnalyzer {
+    /// Push synthetic frames to the listeners at the analysis frame rate from
+    /// a background thread, standing in for audio that couldn't be played
+    pub fn spawn_test_signal(mut self) -> Result<()> {
+        let interval = Duration::from_secs_f32(hop_size(&self.config) as f32 / self.config.sample_rate as f32);
+        thread::Builder::new()
+            .name("test-signal".to_string())
+            .spawn(move || {
+                // The signal's clock only runs while unpaused, so it resumes where it stopped
+                let mut time = 0.0;
+                let mut last_tick = Instant::now();
+                loop {
+                    self.apply_commands();
+                    let now = Instant::now();
+                    if !self.paused {
+                        time += (now - last_tick).as_secs_f32();
+                        let mut frame = self.generate_test_frame(time);
+                        self.sequence += 1;
+                        frame.sequence = self.sequence;
+                        for sink in &self.senders {
+                            sink.send(frame.clone());
+                        }
+                    }
+                    last_tick = now;
+                    thread::sleep(interval);
+                }
+            })?;
+        Ok(())
+    }
+    
     pub fn generate_test_frame(&self, time: f32) -> AudioFrame {
         // Generate synthetic audio response for testing
         let bass = (time * 2.0).sin() * 0.5 + 0.5;
         let mid = (time * 3.0).sin() * 0.5 + 0.5;
         let treble = (time * 5.0).sin() * 0.5 + 0.5;
         
+        let overall_energy = (bass + mid + treble) / 3.0;
+        let (left, right) = match self.mode {
+            AnalysisMode::Mono => (None, None),
+            AnalysisMode::Stereo => {
+                // Sweep the sound slowly from side to side, averaging to the mono bands
+                let pan = (time * 0.5).sin();
+                let side = |gain: f32| ChannelBands {
+                    bass_energy: bass * gain,
+                    mid_energy: mid * gain,
+                    treble_energy: treble * gain,
+                    overall_energy: overall_energy * gain,
+                };
+                (Some(side(1.0 - pan)), Some(side(1.0 + pan)))
+            },
+        };
         AudioFrame {
             bass_energy: bass,
             mid_energy: mid,
             treble_energy: treble,
             peak_frequency: 440.0, // A4 note
-            overall_energy: (bass + mid + treble) / 3.0,
+            overall_energy,
+            left,
+            right,
+            beat: false,
+            treble_onset: false,
+            estimated_bpm: None,
+            energy: EnergyStats { smoothed: overall_energy, baseline: 0.5 },
+            time,
+            spectrum: None,
+            sequence: 0,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+
+/// Seconds of bass history a beat is measured against
+const HISTORY_SECS: f32 = 1.0;
+
+/// How far above the recent average the bass must jump to count as a beat
+const BEAT_THRESHOLD: f32 = 1.5;
+
+/// Shortest gap between beats, so one kick isn't reported twice
+const MIN_BEAT_INTERVAL_SECS: f32 = 0.2;
+
+/// Beat intervals the tempo estimate is taken over
+const TEMPO_INTERVALS: usize = 8;
+
+/// Gaps longer than this are breaks in the music, not beat intervals
+const MAX_BEAT_INTERVAL_SECS: f32 = 2.0;
+
+/// Tempo range estimates are folded into by doubling or halving
+const MIN_BPM: f32 = 70.0;
+const MAX_BPM: f32 = 180.0;
+
+/// Flags bass onsets by comparing each frame against the recent average,
+/// and estimates the tempo from the spacing of those onsets
+pub struct BeatDetector {
+    history: VecDeque<f32>,
+    capacity: usize,  // Frames kept in the history
+    refractory: usize, // Frames to ignore after a beat
+    since_beat: usize,
+    frames_per_second: f32,
+    intervals: VecDeque<usize>, // Frames between recent consecutive beats
+    seen_beat: bool,
+}
+
+impl BeatDetector {
+    /// Detector for frames arriving `frames_per_second` times a second
+    pub fn new(frames_per_second: f32) -> Self {
+        let capacity = (frames_per_second * HISTORY_SECS).ceil().max(1.0) as usize;
+        let refractory = (frames_per_second * MIN_BEAT_INTERVAL_SECS).ceil() as usize;
+        
+        BeatDetector {
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+            refractory,
+            since_beat: refractory,
+            frames_per_second,
+            intervals: VecDeque::with_capacity(TEMPO_INTERVALS),
+            seen_beat: false,
+        }
+    }
+    
+    /// Feed the next frame's bass energy, returning whether it lands on a beat
+    pub fn detect(&mut self, bass_energy: f32) -> bool {
+        let average = if self.history.is_empty() {
+            0.0
+        } else {
+            self.history.iter().sum::<f32>() / self.history.len() as f32
+        };
+        
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(bass_energy);
+        self.since_beat = self.since_beat.saturating_add(1);
+        
+        // Wait for a full second of context before reporting anything
+        let beat = self.history.len() == self.capacity
+            && self.since_beat > self.refractory
+            && bass_energy > average * BEAT_THRESHOLD
+            && bass_energy > f32::EPSILON;
+        if beat {
+            if self.seen_beat && (self.since_beat as f32) < MAX_BEAT_INTERVAL_SECS * self.frames_per_second {
+                if self.intervals.len() == TEMPO_INTERVALS {
+                    self.intervals.pop_front();
+                }
+                self.intervals.push_back(self.since_beat);
+            }
+            self.seen_beat = true;
+            self.since_beat = 0;
+        }
+        beat
+    }
+    
+    /// Tempo from the median of recent beat intervals, once a few beats are in
+    pub fn bpm(&self) -> Option<f32> {
+        if self.intervals.len() < TEMPO_INTERVALS / 2 {
+            return None;
+        }
+        let mut intervals: Vec<usize> = self.intervals.iter().copied().collect();
+        intervals.sort_unstable();
+        let median = intervals[intervals.len() / 2] as f32;
+        
+        // Fold into a musical range; detectors often lock onto half or double time
+        let mut bpm = 60.0 * self.frames_per_second / median;
+        while bpm < MIN_BPM {
+            bpm *= 2.0;
+        }
+        while bpm > MAX_BPM {
+            bpm /= 2.0;
+        }
+        Some(bpm)
+    }
+}
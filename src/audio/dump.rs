@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Records the samples the analyzer runs its FFT over to a 32-bit float WAV
+///
+/// The file has one channel for mono analysis or two for stereo, at the
+/// analysis sample rate, so feeding it back in reproduces the same frames.
+pub struct AudioDump {
+    writer: WavWriter<BufWriter<File>>,
+    channels: u16,
+}
+
+impl AudioDump {
+    pub fn create<P: AsRef<Path>>(path: P, channels: u16, sample_rate: u32) -> Result<Self> {
+        let spec = WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+        let writer = WavWriter::create(path.as_ref(), spec)
+            .with_context(|| format!("Failed to create audio dump {}", path.as_ref().display()))?;
+        
+        Ok(AudioDump { writer, channels })
+    }
+    
+    /// Append per-channel samples, interleaving them to match the file layout
+    ///
+    /// A single channel is duplicated into a stereo file and a pair is averaged
+    /// into a mono one, should the analysis mode change mid-run.
+    pub fn write(&mut self, channels: &[&[f32]]) -> Result<()> {
+        let frames = channels.iter().map(|samples| samples.len()).min().unwrap_or(0);
+        for i in 0..frames {
+            if self.channels == 1 {
+                let sum: f32 = channels.iter().map(|samples| samples[i]).sum();
+                self.writer.write_sample(sum / channels.len() as f32)?;
+            } else {
+                for channel in 0..self.channels as usize {
+                    self.writer.write_sample(channels[channel.min(channels.len() - 1)][i])?;
+                }
+            }
+        }
+        
+        // Keep the header current; the process may exit without dropping the writer
+        self.writer.flush().context("Failed to write audio dump")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use crossbeam_channel::{bounded, Sender};
+use log::warn;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Instant;
+
+use crate::audio::analyzer::AudioFrame;
+
+/// Frames that may queue up before the log starts dropping them
+const LOG_QUEUE_DEPTH: usize = 1024;
+
+/// Start a thread writing every frame sent to the returned channel as a CSV row
+///
+/// Rows are timestamped in seconds since the log was opened, next to the
+/// frame's own position in the audio stream.
+pub fn spawn_analysis_log<P: AsRef<Path>>(path: P) -> Result<Sender<AudioFrame>> {
+    let file = File::create(path.as_ref())
+        .with_context(|| format!("Failed to create analysis log {}", path.as_ref().display()))?;
+    let mut writer = BufWriter::new(file);
+    writeln!(writer, "time_secs,stream_secs,bass_energy,mid_energy,treble_energy,overall_energy,smoothed_energy,baseline_energy,peak_frequency,beat")?;
+    
+    let (sender, receiver) = bounded::<AudioFrame>(LOG_QUEUE_DEPTH);
+    let start = Instant::now();
+    thread::Builder::new()
+        .name("analysis-log".to_string())
+        .spawn(move || {
+            for frame in receiver.iter() {
+                let row = writeln!(
+                    writer,
+                    "{:.4},{:.4},{},{},{},{},{},{},{},{}",
+                    start.elapsed().as_secs_f64(),
+                    frame.time,
+                    frame.bass_energy,
+                    frame.mid_energy,
+                    frame.treble_energy,
+                    frame.overall_energy,
+                    frame.energy.smoothed,
+                    frame.energy.baseline,
+                    frame.peak_frequency,
+                    frame.beat as u8,
+                );
+                // Flush whenever caught up; the process may exit without dropping the senders
+                let written = row.and_then(|_| if receiver.is_empty() { writer.flush() } else { Ok(()) });
+                if let Err(e) = written {
+                    warn!("Stopped writing analysis log: {}", e);
+                    return;
+                }
+            }
+        })
+        .context("Failed to start analysis log thread")?;
+    
+    Ok(sender)
+}
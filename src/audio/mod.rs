@@ -1,2 +1,8 @@
 pub mod player;
-pub mod analyzer;
\ No newline at end of file
+pub mod analyzer;
+pub mod resample;
+pub mod beat;
+pub mod dump;
+pub mod log;
+
+pub use analyzer::{AnalyzerCommand, AudioAnalyzer, AudioFrame, ChannelBands, EnergyStats, Spectrum};
\ No newline at end of file
@@ -1,23 +1,38 @@
-use anyhow::{Result, Context};
-use rodio::{Decoder, OutputStream, Sink, Source};
+use anyhow::{anyhow, Result, Context};
+use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use log::{debug, error, info, warn};
+use rodio::{Decoder, OutputStream, Sample, Sink, Source};
 use std::fs::File;
-use std::io::BufReader;
+use std::collections::VecDeque;
+use std::io::{BufReader, Read, Seek};
+use std::panic::{self, AssertUnwindSafe};
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 use std::io::Cursor;
-use symphonia::core::io::MediaSourceStream;
-use symphonia::core::formats::FormatOptions;
-use symphonia::core::meta::MetadataOptions;
-use symphonia::core::probe::Hint;
 
 use crate::audio::analyzer::AudioAnalyzer;
 use crate::config::Config;
 
+/// Number of interleaved frames handed to the analyzer per block
+const ANALYSIS_BLOCK_SIZE: usize = 1024;
+
+/// Blocks that may queue up before the tap starts dropping them
+const ANALYSIS_QUEUE_DEPTH: usize = 16;
+
 /// Handles audio file loading and playback
 pub struct AudioPlayer {
     _stream: OutputStream,
     sink: Sink,
     config: Arc<Config>,
+    analysis_samples: Option<Receiver<AnalysisBlock>>, // Tapped samples waiting for an analyzer
+    analysis_errors: Option<Receiver<anyhow::Error>>,  // Failure reported by the analysis thread
+    sidechain: Option<Sidechain>, // Analyzed in place of the next track loaded, see `set_analysis_file`
+    played: Arc<AtomicU64>,  // Samples the tap has handed to the sink
+    sample_rate: u32,        // Layout of the loaded track, to turn `played` into time
+    channels: u16,
 }
 
 impl AudioPlayer {
@@ -27,40 +42,133 @@ impl AudioPlayer {
         
         let sink = Sink::try_new(&stream_handle)
             .context("Failed to create audio sink")?;
-            
+        sink.set_volume(config.audio.volume);
+        info!("Opened the default audio output");
+        
         Ok(AudioPlayer {
             _stream: stream,
             sink,
             config,
+            analysis_samples: None,
+            analysis_errors: None,
+            sidechain: None,
+            played: Arc::new(AtomicU64::new(0)),
+            sample_rate: 0,
+            channels: 1,
         })
     }
     
     pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-        // Clear any existing audio
-        self.sink.clear();
-        
         let file = File::open(path.as_ref())
             .context("Failed to open audio file")?;
         
+        self.load_reader(BufReader::new(file))
+    }
+    
+    /// Feed the analyzer from another file (e.g. a drum stem) while the next
+    /// track loaded plays
+    ///
+    /// The side-chain is decoded in step with playback, so frames line up with
+    /// what is heard at the same point in the track. Once it runs out the
+    /// visuals stop following the audio; past the end of playback it is cut off.
+    pub fn set_analysis_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let file = File::open(path.as_ref())
+            .context("Failed to open analysis file")?;
         let source = Decoder::new(BufReader::new(file))
-            .context("Failed to decode audio file")?;
+            .context("Failed to decode analysis file")?;
+        info!("Analyzing {} in place of the played audio", path.as_ref().display());
+        self.sidechain = Some(source);
+        Ok(())
+    }
+    
+    /// Load encoded audio already held in memory, e.g. read from stdin
+    pub fn load_bytes(&mut self, bytes: Vec<u8>) -> Result<()> {
+        self.load_reader(Cursor::new(bytes))
+    }
+    
+    /// Load encoded audio from any seekable reader
+    ///
+    /// The decoder probes the stream and may seek back while detecting the
+    /// format, so non-seekable inputs such as pipes must be buffered first
+    /// (see `load_bytes`). Formats that are only identified by file extension
+    /// cannot be detected from a bare stream.
+    pub fn load_reader<R: Read + Seek + Send + Sync + 'static>(&mut self, reader: R) -> Result<()> {
+        // Clear any existing audio
+        self.sink.clear();
+        
+        let source = Decoder::new(reader)
+            .context("Failed to decode audio")?;
+        info!(
+            "Decoded audio: {} channels at {} Hz, {}",
+            source.channels(),
+            source.sample_rate(),
+            source.total_duration().map_or("length unknown".to_string(), |length| format!("{:.1} s", length.as_secs_f32())),
+        );
             
-        // Prepare the audio source
-        self.sink.append(source);
+        // Whatever the config expects, the analysis follows the file's own layout
+        let analyzed_channels = self.sidechain.as_ref().map_or(source.channels(), |sidechain| sidechain.channels());
+        if analyzed_channels != self.config.audio.channels {
+            warn!(
+                "Audio has {} channels but the config expects {}; analyzing all {}",
+                analyzed_channels, self.config.audio.channels, analyzed_channels,
+            );
+        }
+        
+        // A negative sync offset runs the analysis ahead of what is heard
+        let lead_ms = (-self.config.audio.sync_offset_ms).max(0) as u64;
+        let lookahead = (lead_ms * source.sample_rate() as u64 / 1000) as usize * source.channels() as usize;
+        
+        // Prepare the audio source, tapping its samples for analysis
+        let (sender, receiver) = bounded(ANALYSIS_QUEUE_DEPTH);
+        self.played = Arc::new(AtomicU64::new(0));
+        self.sample_rate = source.sample_rate();
+        self.channels = source.channels().max(1);
+        let mut tap = AnalysisTap::new(source, sender, lookahead, self.played.clone());
+        tap.sidechain = self.sidechain.take();
+        
+        // Open with a little silence so the device is running before the track
+        // starts; the tap counts none of it, so visuals begin with the sound
+        let preroll = Duration::from_millis(self.config.audio.preroll_ms as u64);
+        self.sink.append(tap.delay(preroll));
         self.sink.pause(); // Start paused so we can synchronize with the visualization
+        self.analysis_samples = Some(receiver);
         
         Ok(())
     }
     
-    pub fn play(&mut self, analyzer: AudioAnalyzer) -> Result<()> {
-        // Create a media source from the file
-        // Note: In a real implementation, we'd need to set up audio interceptors 
-        // to feed the analyzer in real-time. This is simplified for the example.
-        
-        // Set up audio analysis
-        // In a full implementation, we'd intercept the audio stream and pass frames to analyzer
+    pub fn play(&mut self, mut analyzer: AudioAnalyzer) -> Result<()> {
+        // Run the analyzer on its own thread, fed by the samples the tap
+        // copies out of the playback stream as the sink consumes them
+        if let Some(samples) = self.analysis_samples.take() {
+            let (error_sender, error_receiver) = bounded(1);
+            thread::Builder::new()
+                .name("audio-analysis".to_string())
+                .spawn(move || {
+                    // Errors and panics end the thread but are reported back
+                    // rather than leaving the visualization silently frozen
+                    let outcome = panic::catch_unwind(AssertUnwindSafe(|| -> Result<()> {
+                        for block in samples {
+                            analyzer.set_stream_time(block.time);
+                            analyzer.set_input_sample_rate(block.sample_rate);
+                            analyzer.process_interleaved(&block.samples, block.channels)
+                                .context("Audio analysis failed")?;
+                        }
+                        Ok(())
+                    }));
+                    let error = match outcome {
+                        Ok(Ok(())) => return,
+                        Ok(Err(error)) => error,
+                        Err(payload) => anyhow!("Audio analysis thread panicked: {}", panic_message(payload.as_ref())),
+                    };
+                    // Nobody is left to show it once the display has closed
+                    if let Err(unsent) = error_sender.send(error) {
+                        error!("{:#}", unsent.0);
+                    }
+                })
+                .context("Failed to start audio analysis thread")?;
+            self.analysis_errors = Some(error_receiver);
+        }
         
-        // For now, just start playback
         self.sink.play();
         
         Ok(())
@@ -70,6 +178,10 @@ impl AudioPlayer {
         self.sink.pause();
     }
     
+    pub fn resume(&mut self) {
+        self.sink.play();
+    }
+    
     pub fn stop(&mut self) {
         self.sink.stop();
     }
@@ -89,4 +201,216 @@ impl AudioPlayer {
     pub fn is_empty(&self) -> bool {
         self.sink.empty()
     }
+    
+    /// How far into the track playback has got, counted from the samples
+    /// handed to the output so it never drifts from the audio
+    ///
+    /// The device's own buffering still plays slightly behind this, which
+    /// `sync_offset_ms` can make up for.
+    pub fn position(&self) -> Duration {
+        let samples_per_second = self.sample_rate as f64 * self.channels as f64;
+        if samples_per_second <= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(self.played.load(Ordering::Relaxed) as f64 / samples_per_second)
+    }
+    
+    /// Error that stopped the analysis thread, if it has failed since the last call
+    pub fn take_error(&self) -> Option<anyhow::Error> {
+        self.analysis_errors.as_ref().and_then(|errors| errors.try_recv().ok())
+    }
+}
+
+/// Readable text from a panic payload
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Decoded file analyzed in place of the played one
+type Sidechain = Decoder<BufReader<File>>;
+
+/// Interleaved samples copied out of the playback stream
+struct AnalysisBlock {
+    channels: u16,
+    sample_rate: u32,
+    time: f64, // Seconds into the track at the block's first sample
+    samples: Vec<f32>,
+}
+
+/// Source adapter that passes samples through to the sink unchanged while
+/// forwarding whole interleaved frames to the analysis thread in fixed blocks
+///
+/// With a non-zero `lookahead` the samples reach the analyzer that many
+/// samples before the sink plays them. With a `sidechain` its samples are
+/// forwarded instead, pulled as fast as the played ones so both stay in step.
+struct AnalysisTap<S: Source>
+where
+    S::Item: Sample,
+{
+    source: S,
+    sender: Sender<AnalysisBlock>,
+    block: AnalysisBlock,
+    frame_pos: u16,  // Channel index within the current interleaved frame
+    lookahead: usize,
+    delayed: VecDeque<S::Item>, // Samples already analyzed but not yet played
+    recorded: f64,              // Seconds of audio copied out for analysis so far
+    played: Arc<AtomicU64>,     // Samples passed on to the sink so far
+    sidechain: Option<Sidechain>, // Source analyzed instead of the played one
+    pulled_pos: u16,            // Channel index within the current played frame, with a side-chain
+    pulled: f64,                // Seconds of the played source pulled so far, with a side-chain
+}
+
+impl<S> AnalysisTap<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn new(source: S, sender: Sender<AnalysisBlock>, lookahead: usize, played: Arc<AtomicU64>) -> Self {
+        AnalysisTap {
+            source,
+            sender,
+            block: AnalysisBlock { channels: 1, sample_rate: 0, time: 0.0, samples: Vec::new() },
+            frame_pos: 0,
+            lookahead,
+            delayed: VecDeque::with_capacity(lookahead + 1),
+            recorded: 0.0,
+            played,
+            sidechain: None,
+            pulled_pos: 0,
+            pulled: 0.0,
+        }
+    }
+    
+    /// Copy one sample, from a source with the given layout, into the current analysis block
+    fn record(&mut self, sample: f32, channels: u16, sample_rate: u32) {
+        // A block only ever holds one channel layout and sample rate
+        if self.frame_pos == 0 {
+            let channels = channels.max(1);
+            if channels != self.block.channels || sample_rate != self.block.sample_rate {
+                if !self.block.samples.is_empty() {
+                    self.flush_block();
+                }
+                self.block.channels = channels;
+                self.block.sample_rate = sample_rate;
+            }
+            if self.block.samples.is_empty() {
+                self.block.time = self.recorded;
+            }
+        }
+        
+        self.block.samples.push(sample);
+        self.frame_pos += 1;
+        if self.frame_pos >= self.block.channels {
+            self.frame_pos = 0;
+            self.recorded += 1.0 / self.block.sample_rate.max(1) as f64;
+            
+            if self.block.samples.len() >= ANALYSIS_BLOCK_SIZE * self.block.channels as usize {
+                self.flush_block();
+            }
+        }
+    }
+    
+    /// Count one more played sample, and at each whole frame record the
+    /// side-chain up to the same point in time
+    fn follow_playback(&mut self) {
+        self.pulled_pos += 1;
+        if self.pulled_pos < self.source.channels().max(1) {
+            return;
+        }
+        self.pulled_pos = 0;
+        self.pulled += 1.0 / self.source.sample_rate().max(1) as f64;
+        
+        while self.recorded < self.pulled {
+            let next = self.sidechain.as_mut()
+                .map(|source| (source.next(), source.channels(), source.sample_rate()));
+            match next {
+                Some((Some(sample), channels, sample_rate)) => self.record(sample.to_f32(), channels, sample_rate),
+                Some((None, _, _)) => {
+                    // The side-chain has ended; hand over its tail and go quiet
+                    if !self.block.samples.is_empty() {
+                        self.flush_block();
+                    }
+                    break;
+                },
+                None => break,
+            }
+        }
+    }
+    
+    fn flush_block(&mut self) {
+        let channels = self.block.channels;
+        let sample_rate = self.block.sample_rate;
+        let samples = Vec::with_capacity(ANALYSIS_BLOCK_SIZE * channels as usize);
+        let block = std::mem::replace(&mut self.block, AnalysisBlock { channels, sample_rate, time: self.recorded, samples });
+        // Never block the audio callback; drop the block if analysis falls behind
+        if let Err(TrySendError::Full(block)) = self.sender.try_send(block) {
+            debug!("Analysis is falling behind; dropped the audio at {:.2} s", block.time);
+        }
+    }
+}
+
+impl<S> Iterator for AnalysisTap<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+    
+    fn next(&mut self) -> Option<S::Item> {
+        // Keep the analysis `lookahead` samples ahead of playback
+        while self.delayed.len() <= self.lookahead {
+            match self.source.next() {
+                Some(sample) => {
+                    if self.sidechain.is_some() {
+                        self.follow_playback();
+                    } else {
+                        self.record(sample.to_f32(), self.source.channels(), self.source.sample_rate());
+                    }
+                    self.delayed.push_back(sample);
+                },
+                None => {
+                    // Hand over the tail of the track so the analyzer can carry it
+                    if !self.block.samples.is_empty() {
+                        self.flush_block();
+                    }
+                    break;
+                },
+            }
+        }
+        
+        let sample = self.delayed.pop_front();
+        if sample.is_some() {
+            self.played.fetch_add(1, Ordering::Relaxed);
+        }
+        sample
+    }
+}
+
+impl<S> Source for AnalysisTap<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        // Samples held back for the lookahead still belong to the current frame
+        self.source.current_frame_len().map(|len| len + self.delayed.len())
+    }
+    
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+    
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+    
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+use std::f32::consts::{PI, TAU};
+
+/// Low-pass filter length per unit of decimation factor; longer filters cut
+/// off more sharply at the new Nyquist frequency
+const DECIMATION_TAPS_PER_FACTOR: usize = 16;
+
+/// Streaming linear-interpolation resampler for one channel
+///
+/// State carries across calls, so a signal split into arbitrary blocks
+/// resamples the same as if it were processed in one piece.
+pub struct LinearResampler {
+    step: f64,              // Input samples advanced per output sample
+    position: f64,          // Fractional position between `previous` and the next input
+    previous: Option<f32>,
+}
+
+impl LinearResampler {
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        LinearResampler {
+            step: from_rate.max(1) as f64 / to_rate.max(1) as f64,
+            position: 0.0,
+            previous: None,
+        }
+    }
+    
+    /// Resample `input`, appending the result to `output`
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        for &sample in input {
+            let previous = match self.previous.replace(sample) {
+                Some(previous) => previous,
+                None => continue,
+            };
+            
+            // Emit every output sample that falls between the last two inputs
+            while self.position < 1.0 {
+                output.push(previous + (sample - previous) * self.position as f32);
+                self.position += self.step;
+            }
+            self.position -= 1.0;
+        }
+    }
+}
+
+/// Streaming integer-factor downsampler for one channel
+///
+/// A windowed-sinc low-pass filter removes everything above the reduced
+/// rate's Nyquist frequency before every `factor`th sample is kept, so
+/// ultrasonic content doesn't fold back into the audible bands.
+pub struct Decimator {
+    factor: usize,
+    taps: Vec<f32>,
+    history: VecDeque<f32>, // The last `taps.len()` inputs, newest at the back
+    phase: usize,           // Inputs since the last kept sample
+}
+
+impl Decimator {
+    pub fn new(factor: u32) -> Self {
+        let factor = factor.max(1) as usize;
+        let len = DECIMATION_TAPS_PER_FACTOR * factor + 1;
+        let cutoff = 0.5 / factor as f32; // In cycles per input sample
+        let center = (len / 2) as f32;
+        let mut taps: Vec<f32> = (0..len)
+            .map(|i| {
+                let t = i as f32 - center;
+                let sinc = if t == 0.0 { 2.0 * cutoff } else { (TAU * cutoff * t).sin() / (PI * t) };
+                let window = 0.5 - 0.5 * (TAU * i as f32 / (len - 1) as f32).cos();
+                sinc * window
+            })
+            .collect();
+        // Unity gain at DC
+        let sum: f32 = taps.iter().sum();
+        for tap in taps.iter_mut() {
+            *tap /= sum;
+        }
+        
+        Decimator {
+            factor,
+            taps,
+            history: std::iter::repeat_n(0.0, len).collect(),
+            phase: 0,
+        }
+    }
+    
+    /// Filter and downsample `input`, appending the result to `output`
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        for &sample in input {
+            self.history.pop_front();
+            self.history.push_back(sample);
+            self.phase += 1;
+            if self.phase == self.factor {
+                self.phase = 0;
+                output.push(self.history.iter().zip(&self.taps).map(|(sample, tap)| sample * tap).sum());
+            }
+        }
+    }
+}
@@ -1,10 +1,17 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::simulation::{patterns, rules};
 
 /// Global configuration for Soundscape Evolution
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Every section and field may be omitted from a config file; missing ones
+/// keep their `Default` values.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub window: WindowConfig,
     pub audio: AudioConfig,
@@ -13,91 +20,582 @@ pub struct Config {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct WindowConfig {
     pub title: String,
     pub width: u32,
     pub height: u32,
     pub fullscreen: bool,
+    pub monitor: Option<usize>,   // Monitor index for fullscreen (current monitor if unset)
+    pub size_from_grid: bool,     // Size the window to the grid times cell_size instead of width/height
+    pub decorations: bool,        // Title bar and borders (off for a borderless overlay)
+    pub always_on_top: bool,      // Keep the window above all others
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            title: "Soundscape Evolution".to_string(),
+            width: 800,
+            height: 600,
+            fullscreen: false,
+            monitor: None,
+            size_from_grid: false,
+            decorations: true,
+            always_on_top: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AudioConfig {
     pub sample_rate: u32,
-    pub channels: u16,
+    pub channels: u16,             // Expected channel count; files with another count are warned about and analyzed as they are
     pub fft_size: usize,
     pub bass_range: (f32, f32),    // Hz range for bass frequencies
     pub mid_range: (f32, f32),     // Hz range for mid frequencies
     pub treble_range: (f32, f32),  // Hz range for treble frequencies
-    pub sensitivity: f32,          // Overall audio sensitivity
+    pub sensitivity: f32,          // Overall audio sensitivity (master multiplier on the bands)
+    pub bass_sensitivity: f32,     // Extra gain on the bass band
+    pub mid_sensitivity: f32,      // Extra gain on the mid band
+    pub treble_sensitivity: f32,   // Extra gain on the treble band
+    pub gate_threshold: f32,       // Band energies below this are treated as silence
+    pub log_amplitude: bool,       // Rescale band energies by their level in dB, so quiet passages still register
+    pub floor_db: f32,             // With log_amplitude, the level mapped to 0 (an energy of 1.0 is 0 dB)
+    pub range_db: f32,             // With log_amplitude, the span above floor_db mapped onto 0-1
+    pub analysis_mode: AnalysisMode, // Mono mixdown or per-channel analysis
+    pub sync_offset_ms: i32,       // Positive delays visuals, negative shows them earlier
+    pub sync_to_playback: bool,    // Show the frame matching the playback position rather than the newest
+    pub preroll_ms: u32,           // Silence played before a track while the output device fills its buffer
+    pub analysis_rate: Option<f32>, // AudioFrames per second (default: one per fft_size window)
+    pub reactivity: f32,           // How strongly the visuals respond to the audio (1.0 is neutral)
+    pub energy_smoothing: f32,     // Seconds for the smoothed overall energy to follow the music
+    pub energy_baseline: f32,      // Seconds for the slow energy baseline to follow the music
+    pub volume: f32,               // Playback volume (1.0 is unchanged)
+    pub decimation: Option<u32>,   // Downsample high-rate input by this factor (anti-aliased) before analysis
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        AudioConfig {
+            sample_rate: 44100,
+            channels: 2,
+            fft_size: 2048,
+            bass_range: (20.0, 250.0),
+            mid_range: (250.0, 2000.0),
+            treble_range: (2000.0, 20000.0),
+            sensitivity: 1.0,
+            bass_sensitivity: 1.0,
+            mid_sensitivity: 1.0,
+            treble_sensitivity: 1.0,
+            gate_threshold: 0.0,
+            log_amplitude: false,
+            floor_db: -20.0,
+            range_db: 60.0,
+            analysis_mode: AnalysisMode::Mono,
+            sync_offset_ms: 0,
+            sync_to_playback: true,
+            preroll_ms: 100,
+            analysis_rate: None,
+            reactivity: 1.0,
+            energy_smoothing: 1.5,
+            energy_baseline: 10.0,
+            volume: 1.0,
+            decimation: None,
+        }
+    }
+}
+
+/// How the analyzer treats multi-channel audio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnalysisMode {
+    Mono,       // Mix all channels down before analysis
+    Stereo,     // Analyze left and right channels separately
+}
+
+impl AnalysisMode {
+    /// The other analysis mode
+    pub fn toggled(self) -> Self {
+        match self {
+            AnalysisMode::Mono => AnalysisMode::Stereo,
+            AnalysisMode::Stereo => AnalysisMode::Mono,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SimulationConfig {
     pub width: usize,
     pub height: usize,
     pub update_rate: f32,         // Updates per second
     pub initial_seed: f32,        // Random seed density (0.0-1.0)
+    pub seed_region: Option<(usize, usize, usize, usize)>, // Random seeding only within (x, y, width, height)
+    #[serde(alias = "topology")]
     pub edge_behavior: EdgeBehavior,
+    pub wrap_x: Option<bool>,     // Wrap left/right, overriding edge_behavior on this axis
+    pub wrap_y: Option<bool>,     // Wrap top/bottom, overriding edge_behavior on this axis
+    pub rule: String,             // Built-in rule name or B/S notation
+    pub rule_zones: Vec<RuleZone>, // Areas running a different rule; the first listed wins where they overlap
+    pub kernel: Option<Vec<Vec<i32>>>, // Neighbor weights centered on the cell, replacing the eight-cell count (odd rows and columns)
+    pub seed: Option<u64>,        // Master seed for reproducible runs (random if unset)
+    pub initial_state: InitialState, // Starting pattern (Random uses initial_seed density)
+    pub initial_pattern: Option<String>, // Plaintext .cells pattern started from instead, centered on an empty grid
+    pub max_lifespan: Option<u16>, // Generations after which cells die of old age
+    pub sticky_age: Option<u16>,   // Cells older than this survive one generation the rule would kill them in
+    pub energy_aging: f32,         // Extra generations of age live cells gain per step at full overall energy (0 = off)
+    pub warmup_generations: u32,   // Generations run without audio at startup, so the grid has settled when the track starts
+    pub generations: bool,        // Allow multi-state Generations rules with dying cells
+    pub audio_affects_rules: bool, // Let audio modulate the rules (colors react either way)
+    pub update_trigger: UpdateTrigger, // Step on a timer, on each detected beat, or following the tempo
+    pub updates_per_beat: f32,    // Generations per beat in Tempo mode (0.5 = every other beat)
+    pub stereo_gravity: f32,      // How strongly growth leans toward the louder channel (0 = off, 1 = strongest)
+    pub mutation_mode: MutationMode, // Which cells audio-driven mutations may flip
+    pub treble_gliders: bool,     // Launch a glider inward from a random edge on treble onsets (e.g. hi-hats)
+    pub glider_cooldown: u32,     // Fewest generations between treble gliders, so dense hats don't flood the grid
+    pub response_curves: ResponseCurves, // How the audio bends the rule's birth and survival thresholds
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            width: 200,
+            height: 150,
+            update_rate: 30.0,
+            initial_seed: 0.3,
+            seed_region: None,
+            edge_behavior: EdgeBehavior::Wrap,
+            wrap_x: None,
+            wrap_y: None,
+            rule: "conway".to_string(),
+            rule_zones: Vec::new(),
+            kernel: None,
+            seed: None,
+            initial_state: InitialState::Random,
+            initial_pattern: None,
+            max_lifespan: None,
+            sticky_age: None,
+            energy_aging: 0.0,
+            warmup_generations: 0,
+            generations: false,
+            audio_affects_rules: true,
+            update_trigger: UpdateTrigger::Timer,
+            updates_per_beat: 2.0,
+            stereo_gravity: 0.0,
+            mutation_mode: MutationMode::Uniform,
+            treble_gliders: false,
+            glider_cooldown: 8,
+            response_curves: ResponseCurves::default(),
+        }
+    }
+}
+
+/// An area of the grid with a rule of its own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleZone {
+    pub region: (usize, usize, usize, usize), // (x, y, width, height) in cells
+    pub rule: String,                         // Built-in rule name or B/S notation (two-state only)
+}
+
+/// What advances the simulation by one generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UpdateTrigger {
+    Timer, // Every 1 / update_rate seconds
+    Beat,  // Once per detected beat
+    Tempo, // updates_per_beat times per beat of the estimated tempo (update_rate until known)
+}
+
+/// How band energy bends the rule when `audio_affects_rules` is on, as
+/// (energy, neighbors) points joined by straight lines and held flat beyond
+/// the first and last
+///
+/// Fractional values are applied by chance: a birth drop of 0.3 lets a cell
+/// one neighbor short of the threshold be born three times in ten.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResponseCurves {
+    pub birth_drop: Vec<(f32, f32)>,     // Bass energy -> neighbors taken off the birth threshold
+    pub survival_shift: Vec<(f32, f32)>, // Mid energy -> neighbors the survival range stretches (negative narrows it)
+}
+
+impl Default for ResponseCurves {
+    fn default() -> Self {
+        // Steps at the original fixed thresholds
+        ResponseCurves {
+            birth_drop: vec![(0.8, 0.0), (0.8, 1.0)],
+            survival_shift: vec![(0.4, -1.0), (0.4, 0.0), (0.7, 0.0), (0.7, 1.0)],
+        }
+    }
+}
+
+/// Where the random flips of audio-driven mutation may land
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MutationMode {
+    Uniform,       // Any cell
+    EdgesOnly,     // Cells in the outermost rows and columns of the grid
+    NearLiveCells, // Cells with at least one live neighbor, so colonies fray at their edges
+}
+
+/// What the grid contains when the simulation starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum InitialState {
+    Random,         // Random cells at the initial_seed density
+    Empty,          // No live cells
+    Acorn,          // Long-lived methuselah
+    RPentomino,     // The classic five-cell methuselah
+    GliderGun,      // Gosper glider gun
+    CenteredBlock,  // A single still-life block in the middle
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EdgeBehavior {
-    Wrap,      // Cells wrap around edges
+    #[serde(alias = "Torus", alias = "torus", alias = "wrap")]
+    Wrap,      // Cells wrap around edges (torus)
+    #[serde(alias = "dead")]
     Dead,      // Cells outside the grid are considered dead
+    #[serde(alias = "alive")]
     Alive,     // Cells outside the grid are considered alive
+    #[serde(alias = "cylinder")]
+    Cylinder,  // Wrap left/right only; top and bottom are dead
+    #[serde(alias = "Klein", alias = "klein", alias = "klein_bottle")]
+    KleinBottle, // Wrap left/right; crossing top/bottom mirrors horizontally
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct VisualizationConfig {
-    pub cell_size: u32,           // Size of each cell in pixels
+    #[serde(default)] // Omitted means fitted, not the default's fixed size
+    pub cell_size: Option<u32>,   // Size of each cell in pixels (fitted to the window if unset)
     pub color_scheme: ColorScheme,
     pub fade_rate: f32,           // Rate at which dead cells fade out
+    pub idle_decay: f32,          // Seconds for colors to settle when audio pauses (0 freezes them)
+    pub dim_floor: f32,           // Brightness during silence (1.0 disables quiet dimming)
+    pub dim_curve: f32,           // Exponent applied to the smoothed energy (<1 brightens faster)
+    pub birth_animation: bool,    // Newborn cells grow and fade in over a few generations
+    pub auto_cycle: Option<AutoCycle>, // Rotate through the color schemes on its own
+    pub trails: bool,             // Fade previous frames out instead of clearing them
+    pub trail_decay: f32,         // Fraction of the way back to the background each frame
+    pub supersample: u8,          // Render at this multiple of the window size and average down (1 = off)
+    pub scheme_params: SchemeParams, // Tuning for the individual color schemes
+    pub mode: VisualizationMode,  // Draw the cells, a radial spectrum, or both
+    pub background_image: Option<PathBuf>, // Picture (PNG or JPEG) drawn behind the cells, scaled to cover the window
+    pub cell_opacity: f32,        // Alpha of live cells, below 1.0 to let the background show through
+    pub beat_flash_ms: u32,       // Time each beat's flash holds before fading (0 disables flashes)
+    pub reset_ages_on_scheme_change: bool, // Restart cell ages whenever the scheme is switched
+    pub scroll_speed: f32,        // Pixels per second the image glides sideways (negative scrolls left)
+    pub scroll_bass: f32,         // Extra scroll speed at full bass, as a multiple of scroll_speed
+    pub zoom_pulse: f32,          // Extra magnification at full bass, so the image breathes (0 = off)
+    pub background_mode: BackgroundMode, // What fills the window behind the cells
+}
+
+impl Default for VisualizationConfig {
+    fn default() -> Self {
+        VisualizationConfig {
+            cell_size: Some(4),
+            color_scheme: ColorScheme::Pulse,
+            fade_rate: 0.1,
+            idle_decay: 2.0,
+            dim_floor: 1.0,
+            dim_curve: 0.5,
+            birth_animation: false,
+            auto_cycle: None,
+            trails: false,
+            trail_decay: 0.15,
+            supersample: 1,
+            scheme_params: SchemeParams::default(),
+            mode: VisualizationMode::Grid,
+            background_image: None,
+            cell_opacity: 1.0,
+            beat_flash_ms: 0,
+            reset_ages_on_scheme_change: false,
+            scroll_speed: 0.0,
+            scroll_bass: 0.0,
+            zoom_pulse: 0.0,
+            background_mode: BackgroundMode::Scheme,
+        }
+    }
+}
+
+/// Tunable constants of the color schemes; anything omitted keeps its default
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SchemeParams {
+    pub heat: HeatParams,
+    pub pulse: PulseParams,
+    pub stepped: SteppedParams,
+}
+
+/// Shape of the Heat gradient from blue (young) to red (old)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeatParams {
+    pub curve: f32,           // Exponent on normalized age (<1 reaches red sooner)
+    pub green_peak: f32,      // Strength of the green through the middle of the gradient
+    pub background: [u8; 3],  // Background RGB
+}
+
+impl Default for HeatParams {
+    fn default() -> Self {
+        HeatParams {
+            curve: 1.0,
+            green_peak: 1.0,
+            background: [0, 0, 20],
+        }
+    }
+}
+
+/// How strongly the Pulse background follows the music
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PulseParams {
+    pub background_reactivity: f32, // Fraction of the overall energy that reaches the background
+    pub background_max: f32,        // Brightest the background gets (0-255), keeping it dark
+    pub channels: [ChannelSource; 3], // What drives the cells' red, green and blue
+}
+
+impl Default for PulseParams {
+    fn default() -> Self {
+        let source = |band| ChannelSource { band, gain: 1.0 };
+        PulseParams {
+            background_reactivity: 0.2,
+            background_max: 30.0,
+            channels: [source(AudioBand::Bass), source(AudioBand::Mid), source(AudioBand::Treble)],
+        }
+    }
 }
 
+/// The band driving one Pulse color channel, and how strongly
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ChannelSource {
+    pub band: AudioBand,
+    pub gain: f32, // Multiplier on the band energy before it is clamped to full brightness
+}
+
+/// One of the analyzed frequency bands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AudioBand {
+    Bass,
+    Mid,
+    Treble,
+}
+
+/// Flat colors for the Stepped scheme, one per age band
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SteppedParams {
+    pub steps: Vec<AgeStep>,  // Bands by starting age; a cell takes the last band it has reached
+    pub background: [u8; 3],  // Background RGB
+}
+
+/// A Stepped color band: cells `age` generations old or older take `color`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgeStep {
+    pub age: u8,
+    pub color: [u8; 3],
+}
+
+impl Default for SteppedParams {
+    fn default() -> Self {
+        let step = |age, color| AgeStep { age, color };
+        SteppedParams {
+            steps: vec![
+                step(1, [250, 240, 210]),
+                step(4, [250, 180, 70]),
+                step(16, [230, 80, 60]),
+                step(64, [140, 50, 120]),
+                step(192, [60, 40, 100]),
+            ],
+            background: [20, 18, 30],
+        }
+    }
+}
+
+/// What fills the window behind the cells, e.g. for compositing into a stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackgroundMode {
+    Scheme,             // The color scheme's own background
+    Solid([u8; 3]),     // A fixed RGB color
+    ChromaKey([u8; 3]), // A key color kept exact (no dimming or beat flashes) so it can be keyed out
+    Transparent,        // A see-through window, with cells composited at their own alpha
+}
+
+/// What gets drawn each frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VisualizationMode {
+    Grid,   // The Game of Life cells
+    Radial, // A circular spectrum of bars around the center
+    Both,   // The radial spectrum drawn over the cells
+}
+
+impl VisualizationMode {
+    /// The mode after this one, wrapping around
+    pub fn next(self) -> VisualizationMode {
+        match self {
+            VisualizationMode::Grid => VisualizationMode::Radial,
+            VisualizationMode::Radial => VisualizationMode::Both,
+            VisualizationMode::Both => VisualizationMode::Grid,
+        }
+    }
+    
+    pub fn shows_grid(self) -> bool {
+        self != VisualizationMode::Radial
+    }
+    
+    pub fn shows_radial(self) -> bool {
+        self != VisualizationMode::Grid
+    }
+}
+
+/// When automatic color scheme cycling moves on to the next scheme
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AutoCycle {
+    TimeInterval(f32), // Seconds per scheme
+    EveryNBeats(u32),  // Detected beats per scheme
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ColorScheme {
     Classic,    // Black and white
     Heat,       // Heat map (blue to red)
     Rainbow,    // Full color spectrum
     Pulse,      // Color changes with audio pulse
+    Stepped,    // Flat colors per age band
+}
+
+impl ColorScheme {
+    /// Every available color scheme, in keybinding order
+    pub const ALL: [ColorScheme; 5] = [
+        ColorScheme::Classic,
+        ColorScheme::Heat,
+        ColorScheme::Rainbow,
+        ColorScheme::Pulse,
+        ColorScheme::Stepped,
+    ];
+    
+    /// The scheme after this one in keybinding order, wrapping around
+    pub fn next(&self) -> ColorScheme {
+        let index = Self::ALL.iter().position(|scheme| scheme == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()].clone()
+    }
+    
+    /// Name as used in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorScheme::Classic => "Classic",
+            ColorScheme::Heat => "Heat",
+            ColorScheme::Rainbow => "Rainbow",
+            ColorScheme::Pulse => "Pulse",
+            ColorScheme::Stepped => "Stepped",
+        }
+    }
+    
+    /// Short human-readable description
+    pub fn description(&self) -> &'static str {
+        match self {
+            ColorScheme::Classic => "Black and white",
+            ColorScheme::Heat => "Heat map from blue (young) to red (old)",
+            ColorScheme::Rainbow => "Full color spectrum by cell age",
+            ColorScheme::Pulse => "Colors pulse with the audio bands",
+            ColorScheme::Stepped => "Flat, posterized colors that change in steps with age",
+        }
+    }
+}
+
+/// The grid dimensions a config file spells out, since defaults hide which were left out
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct GivenGridSize {
+    simulation: GivenDimensions,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct GivenDimensions {
+    width: Option<usize>,
+    height: Option<usize>,
 }
 
 impl Config {
     pub fn from_file(path: &Path) -> Result<Self> {
         let config_str = fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&config_str)?;
+        let mut config: Config = toml::from_str(&config_str)?;
+        let given: GivenGridSize = toml::from_str(&config_str)?;
+        config.fit_grid_to_window(given.simulation.width.is_none(), given.simulation.height.is_none())?;
+        config.validate()?;
         Ok(config)
     }
     
-    pub fn default() -> Self {
-        Config {
-            window: WindowConfig {
-                title: "Soundscape Evolution".to_string(),
-                width: 800,
-                height: 600,
-                fullscreen: false,
-            },
-            audio: AudioConfig {
-                sample_rate: 44100,
-                channels: 2,
-                fft_size: 2048,
-                bass_range: (20.0, 250.0),
-                mid_range: (250.0, 2000.0),
-                treble_range: (2000.0, 20000.0),
-                sensitivity: 1.0,
-            },
-            simulation: SimulationConfig {
-                width: 200,
-                height: 150,
-                update_rate: 30.0,
-                initial_seed: 0.3,
-                edge_behavior: EdgeBehavior::Wrap,
-            },
-            visualization: VisualizationConfig {
-                cell_size: 4,
-                color_scheme: ColorScheme::Pulse,
-                fade_rate: 0.1,
-            },
+    /// Derive the grid's width and/or height from the window size divided by
+    /// `cell_size`, so the grid tiles the window exactly
+    ///
+    /// Nothing changes without a `cell_size`, or with `size_from_grid`, where
+    /// the window follows the grid instead.
+    pub fn fit_grid_to_window(&mut self, width: bool, height: bool) -> Result<()> {
+        let cell_size = match self.visualization.cell_size {
+            Some(size) if size > 0 && !self.window.size_from_grid => size,
+            _ => return Ok(()),
+        };
+        let (window_width, window_height) = (self.window.width, self.window.height);
+        if (width && window_width < cell_size) || (height && window_height < cell_size) {
+            bail!("window {}x{} is too small for a single {}-pixel cell", window_width, window_height, cell_size);
+        }
+        if width {
+            self.simulation.width = (window_width / cell_size) as usize;
+        }
+        if height {
+            self.simulation.height = (window_height / cell_size) as usize;
+        }
+        Ok(())
+    }
+    
+    /// Reject settings that would leave nothing to simulate or draw
+    pub fn validate(&self) -> Result<()> {
+        if self.simulation.width == 0 || self.simulation.height == 0 {
+            bail!("simulation width and height must be at least 1");
+        }
+        if self.window.width == 0 || self.window.height == 0 {
+            bail!("window width and height must be at least 1");
+        }
+        if self.visualization.cell_size == Some(0) {
+            bail!("visualization cell_size must be at least 1");
+        }
+        if self.audio.fft_size == 0 {
+            bail!("audio fft_size must be at least 1");
+        }
+        if self.audio.range_db <= 0.0 {
+            bail!("audio range_db must be positive");
+        }
+        if !(1..=4).contains(&self.visualization.supersample) {
+            bail!("visualization supersample must be between 1 and 4");
+        }
+        if self.simulation.update_rate <= 0.0 {
+            bail!("simulation update_rate must be positive");
+        }
+        let curves = &self.simulation.response_curves;
+        for (name, points) in [("birth_drop", &curves.birth_drop), ("survival_shift", &curves.survival_shift)] {
+            if points.windows(2).any(|pair| pair[1].0 < pair[0].0) {
+                bail!("simulation response_curves {} points must be in order of energy", name);
+            }
+        }
+        for zone in &self.simulation.rule_zones {
+            let ruleset = rules::resolve_rule(&zone.rule).context("simulation rule_zones has an invalid rule")?;
+            if ruleset.states() > 2 {
+                bail!("simulation rule_zones rule '{}' has dying states; zones only take two-state rules", zone.rule);
+            }
+        }
+        if let Some(kernel) = &self.simulation.kernel {
+            let columns = kernel.first().map_or(0, Vec::len);
+            if kernel.len() % 2 == 0 || columns % 2 == 0 {
+                bail!("simulation kernel needs an odd number of rows and columns, got {}x{}", columns, kernel.len());
+            }
+            if kernel.iter().any(|row| row.len() != columns) {
+                bail!("simulation kernel rows must all be the same length");
+            }
+        }
+        if let Some(text) = &self.simulation.initial_pattern {
+            patterns::parse_cells(text).context("simulation initial_pattern is not a valid .cells pattern")?;
         }
+        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,18 @@
+//! Soundscape Evolution library: the audio analysis, Game of Life simulation,
+//! color schemes and configuration behind the visualizer binary.
+//!
+//! ```no_run
+//! use soundscape::audio::AudioAnalyzer;
+//! use soundscape::config::AudioConfig;
+//!
+//! let mut analyzer = AudioAnalyzer::new(AudioConfig::default());
+//! let samples = vec![0.0f32; 4096];
+//! if let Some(frame) = analyzer.process_audio(&samples).unwrap() {
+//!     println!("bass: {}", frame.bass_energy);
+//! }
+//! ```
+
+pub mod audio;
+pub mod config;
+pub mod renderer;
+pub mod simulation;
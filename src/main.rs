@@ -1,19 +1,21 @@
-mod audio;
-mod simulation;
-mod renderer;
-mod config;
-
-use anyhow::{Result, Context};
+use anyhow::{bail, Result, Context};
 use clap::Parser;
-use std::path::PathBuf;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use crossbeam_channel::{bounded, Sender, Receiver};
+use crossbeam_channel::bounded;
+use log::{debug, info, trace, warn, LevelFilter};
 
-use crate::audio::player::AudioPlayer;
-use crate::audio::analyzer::AudioAnalyzer;
-use crate::simulation::gol::GameOfLife;
-use crate::renderer::display::Display;
-use crate::config::Config;
+use soundscape::audio::player::AudioPlayer;
+use soundscape::audio::dump::AudioDump;
+use soundscape::audio::{self, AudioAnalyzer, AudioFrame};
+use soundscape::simulation::gol::{GameOfLife, Rect};
+use soundscape::simulation::patterns;
+use soundscape::simulation::rules::{self, RULE_PRESETS};
+use soundscape::renderer::color::ColorPalette;
+use soundscape::renderer::display::Display;
+use soundscape::renderer::draw::LongExposure;
+use soundscape::config::{AnalysisMode, Config, ColorScheme, InitialState};
 
 /// Soundscape Evolution - Conway's Game of Life visualizer driven by audio
 #[derive(Parser, Debug)]
@@ -23,58 +25,261 @@ struct Args {
     #[arg(short, long)]
     file: Option<PathBuf>,
 
+    /// Read the audio from standard input instead of a file
+    #[arg(long, conflicts_with = "file")]
+    stdin: bool,
+
+    /// Drive the visuals from this file (e.g. a drum stem) while --file or --stdin plays
+    #[arg(long, value_name = "FILE")]
+    analyze: Option<PathBuf>,
+
     /// Path to config file
     #[arg(short, long)]
     config: Option<PathBuf>,
+
+    /// If the audio can't be loaded, warn and run on a synthetic test signal instead of exiting
+    #[arg(long)]
+    ignore_audio_errors: bool,
+
+    /// Record every analyzed audio frame to this CSV file
+    #[arg(long)]
+    analysis_log: Option<PathBuf>,
+
+    /// Write the exact samples the analyzer sees (downmixed and resampled) to a WAV file
+    #[arg(long)]
+    dump_audio: Option<PathBuf>,
+
+    /// Master seed for reproducible initial state and mutations
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Initial grid contents, overriding the config file
+    #[arg(long, value_enum)]
+    initial: Option<InitialState>,
+
+    /// Pattern file (.rle or .cells) to start from, placed in the center of an empty grid
+    #[arg(long)]
+    pattern: Option<PathBuf>,
+
+    /// Run headlessly and save the first generations as one long-exposure PNG, then exit
+    #[arg(long, value_name = "PNG")]
+    long_exposure: Option<PathBuf>,
+
+    /// Generations gathered into the long exposure
+    #[arg(long, default_value_t = 200)]
+    exposure_generations: usize,
+
+    /// Fraction of its light a cell keeps each generation in the long exposure
+    #[arg(long, default_value_t = 0.95)]
+    exposure_decay: f32,
+
+    /// List the available color schemes and exit
+    #[arg(long)]
+    list_schemes: bool,
+
+    /// List the built-in rules with their B/S notation and exit
+    #[arg(long)]
+    list_rules: bool,
+
+    /// Log more detail to stderr: -v for info, -vv for debug, -vvv for trace
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    init_logging(args.verbose);
+    
+    // Introspection flags print and exit before any audio/window setup
+    if args.list_schemes {
+        for scheme in ColorScheme::ALL {
+            println!("{:<10} {}", scheme.name(), scheme.description());
+        }
+        return Ok(());
+    }
+    if args.list_rules {
+        for preset in RULE_PRESETS {
+            println!("{:<10} {:<14} {}", preset.name, preset.notation, preset.description);
+        }
+        return Ok(());
+    }
     
     // Load configuration (either from file or use defaults)
-    let config = match args.config {
-        Some(path) => Config::from_file(&path)?,
-        None => Config::default(),
+    let mut config = match &args.config {
+        Some(path) => {
+            let config = Config::from_file(path)?;
+            info!("Loaded config from {}", path.display());
+            config
+        },
+        None => {
+            debug!("No config file given; using the defaults");
+            Config::default()
+        },
     };
+    if let Some(initial) = args.initial {
+        config.simulation.initial_state = initial;
+    }
+    trace!("Effective config: {:#?}", config);
     let config = Arc::new(config);
     
+    // Initialize game of life simulation; the CLI seed wins over the config one
+    let seed = args.seed
+        .or(config.simulation.seed)
+        .unwrap_or_else(rand::random);
+    info!("Simulation seed: {}", seed);
+    let mut game = GameOfLife::from_config(&config.simulation, seed);
+    let ruleset = rules::resolve_rule(&config.simulation.rule)?;
+    if ruleset.states() > 2 && !config.simulation.generations {
+        bail!(
+            "Rule '{}' has dying states; set `generations = true` under [simulation] to use it",
+            config.simulation.rule,
+        );
+    }
+    game.set_ruleset(ruleset);
+    let zones = config.simulation.rule_zones.iter()
+        .map(|zone| {
+            let (x, y, width, height) = zone.region;
+            Ok((Rect { x, y, width, height }, rules::resolve_rule(&zone.rule)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    game.set_rule_zones(zones);
+    if let Some(path) = &args.pattern {
+        let pattern = patterns::load_pattern(path)?;
+        let (pattern_width, pattern_height) = pattern.size();
+        game.clear();
+        game.stamp_pattern(
+            &pattern.cells,
+            game.width().saturating_sub(pattern_width) / 2,
+            game.height().saturating_sub(pattern_height) / 2,
+        );
+    }
+    
+    // Let the grid settle before the audio gets a say
+    for _ in 0..config.simulation.warmup_generations {
+        game.update(None);
+    }
+    
+    // Long exposures need neither the window nor the audio devices
+    if let Some(path) = &args.long_exposure {
+        return save_long_exposure(&config, game, path, args.exposure_generations, args.exposure_decay);
+    }
+    let simulation = Arc::new(Mutex::new(game));
+    
     // Create channels for communication between audio and visualization
-    let (audio_sender, audio_receiver) = bounded::<audio::analyzer::AudioFrame>(2);
+    let (audio_sender, audio_receiver) = bounded::<AudioFrame>(2);
     
     // Initialize components
     let mut player = AudioPlayer::new(config.clone())?;
-    let analyzer = AudioAnalyzer::new(config.clone(), audio_sender);
-    
-    // Initialize game of life simulation
-    let simulation = Arc::new(Mutex::new(
-        GameOfLife::new(
-            config.simulation.width,
-            config.simulation.height,
-            config.simulation.initial_seed,
-        )
-    ));
+    // The display may stall (e.g. while the window is dragged); it resumes with the newest frames
+    let mut analyzer = AudioAnalyzer::new(config.audio.clone());
+    analyzer.add_latest_sender(audio_sender, audio_receiver.clone());
+    if let Some(path) = &args.analysis_log {
+        analyzer.add_sender(audio::log::spawn_analysis_log(path)?);
+    }
+    if let Some(path) = &args.dump_audio {
+        let channels = match config.audio.analysis_mode {
+            AnalysisMode::Mono => 1,
+            AnalysisMode::Stereo => 2,
+        };
+        analyzer.set_dump(AudioDump::create(path, channels, config.audio.sample_rate)?);
+    }
+    let analyzer_commands = analyzer.command_sender();
 
     // Initialize the display/renderer
     let mut display = Display::new(
         config.clone(),
         simulation.clone(),
         audio_receiver,
+        analyzer_commands,
     )?;
 
-    // If audio file was provided, load it
-    if let Some(file_path) = args.file {
-        player.load_file(&file_path)
-            .context("Failed to load audio file")?;
-        
-        // Start the audio playback with analyzer callback
-        player.play(analyzer)?;
-    } else {
-        println!("No audio file specified. Use --file to specify an audio file.");
-        println!("Running with just the Game of Life simulation.");
+    // If audio was provided, load it
+    match load_audio(&mut player, &args) {
+        Ok(true) => {
+            // Start the audio playback with analyzer callback
+            player.play(analyzer)?;
+            display.set_player(player);
+        },
+        Ok(false) => {
+            // A demo rather than a grid that ignores the audio-driven settings
+            warn!("No audio specified; use --file or --stdin to provide audio");
+            info!("Running a demo on a synthetic test signal");
+            analyzer.spawn_test_signal()?;
+        },
+        Err(e) if args.ignore_audio_errors => {
+            warn!("{:#}", e);
+            warn!("Running with a synthetic test signal instead");
+            analyzer.spawn_test_signal()?;
+        },
+        Err(e) => return Err(e),
     }
-
+    
     // Run the display/renderer (this will block until the window is closed)
     display.run()?;
+    
+    Ok(())
+}
 
+/// Send log output to stderr: warnings alone by default, more with each -v
+fn init_logging(verbosity: u8) {
+    let level = match verbosity {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    // RUST_LOG, when set, can still tune individual modules
+    env_logger::Builder::new()
+        .filter_level(level)
+        .parse_default_env()
+        .init();
+}
+
+/// Run `generations` generations against the synthetic test signal, paced at
+/// the configured update rate, and save them as one long-exposure PNG
+fn save_long_exposure(config: &Config, mut game: GameOfLife, path: &Path, generations: usize, decay: f32) -> Result<()> {
+    let analyzer = AudioAnalyzer::new(config.audio.clone());
+    let step_secs = 1.0 / config.simulation.update_rate.max(f32::EPSILON);
+    let frames = (1..).map(|i| analyzer.generate_test_frame(i as f32 * step_secs));
+    let mut exposure = LongExposure::new(game.width(), game.height(), decay);
+    for snapshot in game.generations(frames).take(generations) {
+        exposure.expose(&snapshot);
+    }
+    
+    let mut palette = ColorPalette::new(config.visualization.color_scheme.clone());
+    palette.set_scheme_params(config.visualization.scheme_params.clone());
+    let (rgba, layout) = exposure.render(config, &palette);
+    let (width, height) = layout.frame_size;
+    image::RgbaImage::from_raw(width as u32, height as u32, rgba)
+        .context("Exposure buffer doesn't match its size")?
+        .save(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    info!("Saved a {}-generation long exposure to {}", generations, path.display());
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Load the audio named on the command line, returning false if none was given
+fn load_audio(player: &mut AudioPlayer, args: &Args) -> Result<bool> {
+    if let Some(path) = &args.analyze {
+        if args.file.is_none() && !args.stdin {
+            bail!("--analyze needs the audio to play from --file or --stdin");
+        }
+        player.set_analysis_file(path)
+            .with_context(|| format!("Failed to load analysis file {}", path.display()))?;
+    }
+    if let Some(file_path) = &args.file {
+        player.load_file(file_path)
+            .context("Failed to load audio file")?;
+        Ok(true)
+    } else if args.stdin {
+        // Pipes can't seek, so buffer the whole stream for format detection
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)
+            .context("Failed to read audio from stdin")?;
+        player.load_bytes(bytes)
+            .context("Failed to load audio from stdin")?;
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
@@ -1,8 +1,8 @@
-use crate::config::ColorScheme;
+use crate::config::{AudioBand, BackgroundMode, ColorScheme, SchemeParams};
 use crate::audio::analyzer::AudioFrame;
 
 /// RGB color representation
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -41,6 +41,13 @@ impl Color {
         }
     }
     
+    /// Mix toward `other` by `t` (0.0 = self, 1.0 = other)
+    pub fn blend(&self, other: Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b), mix(self.a, other.a))
+    }
+    
     /// Fade the color by a given factor (0.0 = transparent, 1.0 = opaque)
     pub fn fade(&self, factor: f32) -> Self {
         let alpha = (self.a as f32 * factor.clamp(0.0, 1.0)) as u8;
@@ -48,37 +55,157 @@ impl Color {
     }
 }
 
+/// Seconds without a fresh audio frame before the palette counts as idle
+const IDLE_AFTER_SECS: f32 = 0.25;
+
+/// Peak band energy of the gentle breathing shown while idle
+const IDLE_BREATH_LEVEL: f32 = 0.15;
+
 /// ColorPalette handles different color schemes for the visualization
 pub struct ColorPalette {
     scheme: ColorScheme,
     time: f32,                // Used for time-based effects
     audio_frame: Option<AudioFrame>, // Current audio frame for reactive effects
+    idle_time: f32,           // Seconds since the last fresh audio frame
+    idle_decay: f32,          // Seconds for energies to settle once idle (0 freezes them)
+    params: SchemeParams,     // Per-scheme tuning from the config
+    background: Option<Color>, // Replaces the scheme's background, see `set_background_mode`
+    table: Option<Box<[Color; 256]>>, // Cell color by age for schemes that ignore the audio
 }
 
 impl ColorPalette {
     pub fn new(scheme: ColorScheme) -> Self {
-        ColorPalette {
+        let mut palette = ColorPalette {
             scheme,
             time: 0.0,
             audio_frame: None,
-        }
+            idle_time: 0.0,
+            idle_decay: 0.0,
+            params: SchemeParams::default(),
+            background: None,
+            table: None,
+        };
+        palette.build_table();
+        palette
     }
     
-    /// Update the palette with new audio data and time
+    /// Update the palette with a fresh audio frame (if one arrived) and time
     pub fn update(&mut self, audio_frame: Option<&AudioFrame>, delta_time: f32) {
+        self.advance(delta_time);
         if let Some(frame) = audio_frame {
-            self.audio_frame = Some(frame.clone());
+            self.set_audio_frame(frame.clone());
         }
+    }
+    
+    /// Take over a freshly analyzed audio frame
+    pub fn set_audio_frame(&mut self, audio_frame: AudioFrame) {
+        self.audio_frame = Some(audio_frame);
+        self.idle_time = 0.0;
+    }
+    
+    /// Advance time-based effects, once per rendered frame
+    ///
+    /// When no fresh frame arrives for a while (e.g. playback paused) the last
+    /// energies decay toward zero and a slow time-based breathing takes over.
+    pub fn advance(&mut self, delta_time: f32) {
         self.time += delta_time;
+        self.idle_time += delta_time;
+        if self.idle_time > IDLE_AFTER_SECS && self.idle_decay > 0.0 {
+            if let Some(frame) = &mut self.audio_frame {
+                let decay = (-delta_time / self.idle_decay).exp();
+                frame.bass_energy *= decay;
+                frame.mid_energy *= decay;
+                frame.treble_energy *= decay;
+                frame.overall_energy *= decay;
+            }
+        }
+    }
+    
+    /// Set how long energies take to settle when audio stops (0 freezes them)
+    pub fn set_idle_decay(&mut self, seconds: f32) {
+        self.idle_decay = seconds.max(0.0);
+    }
+    
+    /// Fill the background with a fixed color, or fully transparent pixels,
+    /// instead of the scheme's own
+    pub fn set_background_mode(&mut self, mode: BackgroundMode) {
+        self.background = match mode {
+            BackgroundMode::Scheme => None,
+            BackgroundMode::Solid([r, g, b]) | BackgroundMode::ChromaKey([r, g, b]) => Some(Color::new(r, g, b, 255)),
+            BackgroundMode::Transparent => Some(Color::new(0, 0, 0, 0)),
+        };
+    }
+    
+    /// Replace the per-scheme tuning
+    pub fn set_scheme_params(&mut self, params: SchemeParams) {
+        self.params = params;
+        self.build_table();
+    }
+    
+    /// Precompute the color of every age at full scale, so drawing a cell is a
+    /// lookup; Pulse follows the live audio and is always computed
+    fn build_table(&mut self) {
+        self.table = None;
+        if self.scheme == ColorScheme::Pulse {
+            return;
+        }
+        let mut table = Box::new([Color::black(); 256]);
+        for (age, color) in table.iter_mut().enumerate() {
+            *color = self.compute_cell_color(age as u8, u8::MAX);
+        }
+        self.table = Some(table);
+    }
+    
+    /// Per-band breathing added while idle, fading in as the energies settle
+    fn idle_breath(&self) -> [f32; 3] {
+        if self.idle_decay <= 0.0 || self.idle_time <= IDLE_AFTER_SECS {
+            return [0.0; 3];
+        }
+        
+        let fade_in = ((self.idle_time - IDLE_AFTER_SECS) / self.idle_decay).min(1.0);
+        let wave = |phase: f32| 0.5 + 0.5 * (self.time * 0.8 + phase).sin();
+        [
+            IDLE_BREATH_LEVEL * fade_in * wave(0.0),
+            IDLE_BREATH_LEVEL * fade_in * wave(2.1),
+            IDLE_BREATH_LEVEL * fade_in * wave(4.2),
+        ]
+    }
+    
+    /// Copy of this palette reacting to a different audio frame, e.g. one stereo channel
+    pub fn with_audio_frame(&self, audio_frame: AudioFrame) -> Self {
+        ColorPalette {
+            scheme: self.scheme.clone(),
+            time: self.time,
+            audio_frame: Some(audio_frame),
+            idle_time: self.idle_time,
+            idle_decay: self.idle_decay,
+            params: self.params.clone(),
+            background: self.background,
+            table: self.table.clone(),
+        }
+    }
+    
+    /// Current color scheme
+    pub fn scheme(&self) -> &ColorScheme {
+        &self.scheme
     }
     
     /// Set color scheme
     pub fn set_scheme(&mut self, scheme: ColorScheme) {
         self.scheme = scheme;
+        self.build_table();
     }
     
     /// Get cell color based on its age and the current color scheme
     pub fn get_cell_color(&self, age: u8, max_age: u8) -> Color {
+        match &self.table {
+            Some(table) if max_age == u8::MAX => table[age as usize],
+            _ => self.compute_cell_color(age, max_age),
+        }
+    }
+    
+    /// Cell color worked out from the scheme, bypassing the lookup table
+    pub fn compute_cell_color(&self, age: u8, max_age: u8) -> Color {
         match self.scheme {
             ColorScheme::Classic => {
                 // Simple black and white
@@ -94,9 +221,10 @@ impl ColorPalette {
                     return Color::black();
                 }
                 
-                let normalized_age = age as f32 / max_age as f32;
+                let heat = &self.params.heat;
+                let normalized_age = (age as f32 / max_age as f32).powf(heat.curve.max(0.01));
                 let r = (normalized_age * 255.0) as u8;
-                let g = ((1.0 - normalized_age) * 255.0 * normalized_age) as u8;
+                let g = ((1.0 - normalized_age) * 255.0 * normalized_age * heat.green_peak).clamp(0.0, 255.0) as u8;
                 let b = ((1.0 - normalized_age) * 255.0) as u8;
                 
                 Color::new(r, g, b, 255)
@@ -132,17 +260,24 @@ impl ColorPalette {
                 
                 if let Some(ref frame) = self.audio_frame {
                     // Use audio energy to influence colors
-                    let bass = frame.bass_energy.clamp(0.0, 1.0);
-                    let mid = frame.mid_energy.clamp(0.0, 1.0);
-                    let treble = frame.treble_energy.clamp(0.0, 1.0);
+                    let [bass_breath, mid_breath, treble_breath] = self.idle_breath();
+                    let bass = (frame.bass_energy + bass_breath).clamp(0.0, 1.0);
+                    let mid = (frame.mid_energy + mid_breath).clamp(0.0, 1.0);
+                    let treble = (frame.treble_energy + treble_breath).clamp(0.0, 1.0);
                     
                     // Age affects color intensity
                     let intensity = (age as f32 / max_age as f32).min(1.0);
                     
-                    // Create pulsing colors based on audio bands
-                    let r = (bass * 255.0 * intensity) as u8;
-                    let g = (mid * 255.0 * intensity) as u8;
-                    let b = (treble * 255.0 * intensity) as u8;
+                    // Create pulsing colors based on audio bands, each channel
+                    // following the band the config assigns it
+                    let [r, g, b] = self.params.pulse.channels.map(|source| {
+                        let level = match source.band {
+                            AudioBand::Bass => bass,
+                            AudioBand::Mid => mid,
+                            AudioBand::Treble => treble,
+                        };
+                        ((level * source.gain).clamp(0.0, 1.0) * 255.0 * intensity) as u8
+                    });
                     
                     Color::new(r, g, b, 255)
                 } else {
@@ -150,20 +285,47 @@ impl ColorPalette {
                     Color::white()
                 }
             },
+            ColorScheme::Stepped => {
+                // Flat color of the latest band the cell's age has reached
+                if age == 0 {
+                    return Color::black();
+                }
+                
+                let steps = &self.params.stepped.steps;
+                let step = steps.iter()
+                    .filter(|step| step.age <= age)
+                    .max_by_key(|step| step.age)
+                    .or_else(|| steps.iter().min_by_key(|step| step.age));
+                match step {
+                    Some(step) => Color::new(step.color[0], step.color[1], step.color[2], 255),
+                    None => Color::white(),
+                }
+            },
         }
     }
     
     /// Get background color based on audio energy
     pub fn get_background_color(&self) -> Color {
+        if let Some(background) = self.background {
+            return background;
+        }
         match self.scheme {
             ColorScheme::Classic => Color::black(),
-            ColorScheme::Heat => Color::new(0, 0, 20, 255), // Dark blue
+            ColorScheme::Heat => {
+                let [r, g, b] = self.params.heat.background; // Dark blue by default
+                Color::new(r, g, b, 255)
+            },
             ColorScheme::Rainbow => Color::black(),
+            ColorScheme::Stepped => {
+                let [r, g, b] = self.params.stepped.background;
+                Color::new(r, g, b, 255)
+            },
             ColorScheme::Pulse => {
                 if let Some(ref frame) = self.audio_frame {
                     // Subtle background pulse with the audio
-                    let energy = frame.overall_energy * 0.2; // Subtle effect
-                    let value = (energy * 30.0) as u8; // Max 30 to keep it dark
+                    let pulse = &self.params.pulse;
+                    let energy = frame.overall_energy * pulse.background_reactivity; // Subtle effect
+                    let value = (energy * pulse.background_max).clamp(0.0, 255.0) as u8; // Kept dark by default
                     Color::new(value, value, value, 255)
                 } else {
                     Color::black()
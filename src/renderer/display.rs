@@ -1,26 +1,128 @@
 use anyhow::{Result, Context};
-use crossbeam_channel::Receiver;
-use pixels::{Pixels, SurfaceTexture};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use log::{debug, error, info, trace, warn};
+use pixels::{wgpu, Pixels, PixelsBuilder, SurfaceTexture};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::collections::VecDeque;
+use std::fs;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use winit::dpi::LogicalSize;
-use winit::event::{Event, WindowEvent, KeyboardInput, VirtualKeyCode, ElementState};
+use winit::event::{Event, WindowEvent, KeyEvent, ElementState, MouseButton, MouseScrollDelta, Touch, TouchPhase};
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::window::{WindowBuilder, Fullscreen};
+use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
+use winit::window::{WindowBuilder, Fullscreen, WindowLevel};
 
-use crate::audio::analyzer::AudioFrame;
-use crate::config::{Config, ColorScheme};
-use crate::renderer::color::ColorPalette;
-use crate::simulation::gol::GameOfLife;
+use crate::audio::analyzer::{AnalyzerCommand, AudioFrame, Spectrum};
+use crate::audio::player::AudioPlayer;
+use crate::config::{AnalysisMode, AutoCycle, BackgroundMode, Config, ColorScheme, UpdateTrigger, VisualizationMode};
+use crate::renderer::color::{Color, ColorPalette};
+use crate::renderer::hud::{self, History};
+use crate::renderer::draw::{self, BackgroundImage, CellCache, Layout};
+use crate::renderer::scene::{self, SceneState, SCENE_SLOTS};
+use crate::simulation::gol::{self, GameOfLife, GridSnapshot};
+use crate::simulation::patterns::{self, Pattern};
+use crate::simulation::rules::{self, RULE_PRESETS};
+use crate::simulation::symmetry::Symmetry;
+
+/// Largest brush radius reachable with `]` or the scroll wheel
+const MAX_BRUSH_RADIUS: usize = 32;
+
+/// Longest frame time animations advance by in one step, in seconds
+const MAX_FRAME_DELTA: f32 = 0.1;
+
+/// Bounds on the update rate derived from the tempo, in generations per second
+const MIN_TEMPO_UPDATE_RATE: f32 = 0.5;
+const MAX_TEMPO_UPDATE_RATE: f32 = 60.0;
+
+/// Pixel scale of the inspection tooltip's font
+const TOOLTIP_SCALE: usize = 2;
+
+/// How long a status label stays on screen, and its font scale
+const LABEL_SECS: f32 = 3.0;
+const LABEL_SCALE: usize = 2;
+
+/// Scheme interval used when `T` enables cycling without one configured
+const DEFAULT_AUTO_CYCLE_SECS: f32 = 30.0;
+
+/// Reactivity bounds and multiplicative step for `,` and `.`
+const MIN_REACTIVITY: f32 = 0.1;
+const MAX_REACTIVITY: f32 = 10.0;
+const REACTIVITY_STEP: f32 = 1.25;
+
+/// Seeding density bounds and step for `Shift+Up` and `Shift+Down`
+const MIN_DENSITY: f32 = 0.05;
+const MAX_DENSITY: f32 = 0.95;
+const DENSITY_STEP: f32 = 0.05;
+
+/// Largest configurable `zoom_pulse`, and how quickly the zoom follows the
+/// bass so it eases in and out instead of jumping with every frame
+const MAX_ZOOM_PULSE: f32 = 0.5;
+const ZOOM_SMOOTHING_SECS: f32 = 0.08;
+
+/// Zoom this close to 1.0 counts as settled, so frames can be skipped again
+const ZOOM_SETTLED: f32 = 0.001;
+
+/// Playback volume bounds and step for `-` and `=`
+const MAX_VOLUME: f32 = 2.0;
+const VOLUME_STEP: f32 = 0.1;
+
+/// Samples kept for the statistics graph, one per rendered frame (about 10 s at 60 FPS)
+const GRAPH_SAMPLES: usize = 600;
+const GRAPH_WIDTH: usize = 240;
+const GRAPH_HEIGHT: usize = 60;
+const GRAPH_SCALE: usize = 2;
+
+/// Requests from outside the window (e.g. a touch or OSC bridge) for a running display
+#[derive(Debug, Clone)]
+pub enum DisplayCommand {
+    ToggleCell { x: f32, y: f32 }, // Flip the cell at this position across the grid, each 0.0-1.0
+}
 
 pub struct Display {
-    config: Arc<Config>,
+    config: Config, // Effective settings, with runtime tweaks written back
+    
     simulation: Arc<Mutex<GameOfLife>>,
     audio_receiver: Receiver<AudioFrame>,
     color_palette: ColorPalette,
     last_frame_time: Instant,
     last_sim_update: Instant,
     current_audio_frame: Option<AudioFrame>,
+    holding_frame: bool,                             // Keep the current frame, ignoring live ones, while tuning colors
+    pending_frames: VecDeque<(Instant, AudioFrame)>, // Frames waiting for their sync-adjusted release time
+    last_sequence: u64,                              // Sequence number of the newest frame received, to ignore repeats
+    frame_delay: Duration,                           // Positive sync offset applied to incoming frames
+    rule_index: Option<usize>, // Position in RULE_PRESETS when cycling rules
+    analyzer_commands: Sender<AnalyzerCommand>,
+    commands: Receiver<DisplayCommand>,
+    command_sender: Sender<DisplayCommand>,
+    cursor_cell: Option<(usize, usize)>, // Grid cell under the mouse cursor
+    drawing: Option<bool>,               // Cell state being painted while a button is held
+    brush_radius: usize,
+    symmetry: Symmetry,                  // Mirroring applied while drawing
+    modifiers: ModifiersState,
+    player: Option<AudioPlayer>, // Playback being visualized, if any
+    paused: bool,                // Simulation (and playback) paused
+    cycle_interval: AutoCycle,   // Scheme cycling restored when T turns it back on
+    cycle_elapsed: f32,          // Seconds since the scheme last changed
+    cycle_beats: u32,            // Beats since the scheme last changed
+    beat_flash_age: Option<f32>, // Seconds since the beat whose flash is showing
+    scroll_offset: f32,          // Pixels the image is shifted right by scroll_speed, within the frame width
+    zoom: f32,                   // Current bass-driven magnification of the image (1.0 = none)
+    trail_buffer: Vec<u8>,       // Last composited frame, faded toward the background for trails
+    supersample_buffer: Vec<u8>, // High-resolution frame averaged down when supersampling
+    cell_cache: CellCache,       // What the window's frame buffer shows, to repaint only changed cells
+    inspecting: bool,            // Show the stats of the cell under the cursor
+    library: Vec<Pattern>,       // Bundled patterns that can be stamped at the cursor
+    library_index: usize,        // Pattern stamped by Enter
+    label: Option<(String, Instant)>, // Status text shown briefly in the corner
+    layout: Layout,              // Frame size, cell size and letterboxing of the grid
+    background_image: Option<BackgroundImage>, // Picture behind the cells, at the frame size
+    scenes: Vec<Option<SceneState>>, // Saved scene slots, filled from disk on first use
+    start_state: Option<GridSnapshot>, // Grid right after the last reseed, stamp or scene load
+    show_graph: bool,                  // Plot recent population and energy in the bottom-left corner
+    population_history: History,       // Live cells at each rendered frame
+    energy_history: History,           // Overall audio energy at each rendered frame
+    redraw_needed: bool,               // Input or a setting changed what should be on screen
 }
 
 impl Display {
@@ -28,190 +130,1253 @@ impl Display {
         config: Arc<Config>,
         simulation: Arc<Mutex<GameOfLife>>,
         audio_receiver: Receiver<AudioFrame>,
+        analyzer_commands: Sender<AnalyzerCommand>,
     ) -> Result<Self> {
-        let color_palette = ColorPalette::new(config.visualization.color_scheme.clone());
+        let mut color_palette = ColorPalette::new(config.visualization.color_scheme.clone());
+        color_palette.set_idle_decay(config.visualization.idle_decay);
+        color_palette.set_scheme_params(config.visualization.scheme_params.clone());
+        color_palette.set_background_mode(config.visualization.background_mode);
+        let rule_index = rules::find_preset(&config.simulation.rule);
+        let frame_delay = Duration::from_millis(config.audio.sync_offset_ms.max(0) as u64);
+        let cycle_interval = config.visualization.auto_cycle
+            .unwrap_or(AutoCycle::TimeInterval(DEFAULT_AUTO_CYCLE_SECS));
+        let grid_size = simulation.lock()
+            .map(|sim| (sim.width(), sim.height()))
+            .unwrap_or((config.simulation.width, config.simulation.height));
+        let layout = Layout::new(&config, grid_size);
+        let start_state = simulation.lock().ok().map(|sim| sim.snapshot());
+        let background_image = config.visualization.background_image.as_ref()
+            .map(|path| BackgroundImage::load(path, layout.frame_size))
+            .transpose()?;
+        let (command_sender, commands) = unbounded();
         
         Ok(Display {
-            config,
+            config: (*config).clone(),
             simulation,
             audio_receiver,
             color_palette,
             last_frame_time: Instant::now(),
             last_sim_update: Instant::now(),
             current_audio_frame: None,
+            holding_frame: false,
+            pending_frames: VecDeque::new(),
+            last_sequence: 0,
+            frame_delay,
+            rule_index,
+            analyzer_commands,
+            commands,
+            command_sender,
+            cursor_cell: None,
+            drawing: None,
+            brush_radius: 0,
+            symmetry: Symmetry::None,
+            modifiers: ModifiersState::empty(),
+            player: None,
+            paused: false,
+            cycle_interval,
+            cycle_elapsed: 0.0,
+            cycle_beats: 0,
+            beat_flash_age: None,
+            scroll_offset: 0.0,
+            zoom: 1.0,
+            trail_buffer: Vec::new(),
+            supersample_buffer: Vec::new(),
+            cell_cache: CellCache::new(),
+            inspecting: false,
+            library: patterns::library(),
+            library_index: 0,
+            label: None,
+            layout,
+            background_image,
+            scenes: vec![None; SCENE_SLOTS],
+            start_state,
+            show_graph: false,
+            population_history: History::new(GRAPH_SAMPLES),
+            energy_history: History::new(GRAPH_SAMPLES),
+            redraw_needed: true,
         })
     }
     
+    /// Refit the grid to a resized window when no cell size is configured
+    ///
+    /// Cells stay square at the largest whole size that fits, so a grid whose
+    /// aspect differs from the window's is centered between background bars
+    /// rather than stretched. A fixed cell size is left for pixels to scale.
+    fn refit(&mut self, pixels: &mut Pixels, size: LogicalSize<u32>) -> Result<()> {
+        if self.config.visualization.cell_size.is_some() || size.width == 0 || size.height == 0 {
+            return Ok(());
+        }
+        
+        self.config.window.width = size.width;
+        self.config.window.height = size.height;
+        let grid_size = match self.simulation.lock() {
+            Ok(sim) => (sim.width(), sim.height()),
+            Err(_) => return Ok(()),
+        };
+        let layout = Layout::new(&self.config, grid_size);
+        if layout == self.layout {
+            return Ok(());
+        }
+        
+        pixels.resize_buffer(layout.frame_size.0 as u32, layout.frame_size.1 as u32)
+            .context("Failed to resize pixel buffer")?;
+        if let Some(path) = &self.config.visualization.background_image {
+            self.background_image = Some(BackgroundImage::load(path, layout.frame_size)?);
+        }
+        debug!("Refit {}x{} grid to {}x{} window at {} px per cell", grid_size.0, grid_size.1, size.width, size.height, layout.cell_size);
+        self.layout = layout;
+        self.cell_cache.invalidate();
+        self.trail_buffer.clear();
+        self.redraw_needed = true;
+        Ok(())
+    }
+    
+    /// Hand over the audio player so playback can be controlled from the window
+    pub fn set_player(&mut self, player: AudioPlayer) {
+        self.player = Some(player);
+    }
+    
     pub fn run(&mut self) -> Result<()> {
-        let event_loop = EventLoop::new();
+        let event_loop = EventLoop::new().context("Failed to create event loop")?;
         
         // Create window
-        let window_width = self.config.window.width;
-        let window_height = self.config.window.height;
+        let window_width = self.layout.frame_size.0 as u32;
+        let window_height = self.layout.frame_size.1 as u32;
         
         let window = WindowBuilder::new()
             .with_title(&self.config.window.title)
             .with_inner_size(LogicalSize::new(window_width, window_height))
             .with_resizable(true)
+            .with_decorations(self.config.window.decorations)
+            .with_transparent(self.is_transparent())
+            .with_window_level(if self.config.window.always_on_top { WindowLevel::AlwaysOnTop } else { WindowLevel::Normal })
             .build(&event_loop)
             .context("Failed to create window")?;
             
         // Set fullscreen if configured
         if self.config.window.fullscreen {
-            window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+            window.set_fullscreen(Some(self.fullscreen_mode(&window)));
         }
         
         // Create pixel buffer
         let surface_texture = SurfaceTexture::new(window_width, window_height, &window);
-        let mut pixels = Pixels::new(window_width, window_height, surface_texture)
+        let mut builder = PixelsBuilder::new(window_width, window_height, surface_texture);
+        if self.is_transparent() {
+            // Let the desktop show through wherever the frame is transparent
+            builder = builder
+                .clear_color(wgpu::Color::TRANSPARENT)
+                .blend_state(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING);
+        }
+        let mut pixels = builder.build()
             .context("Failed to create pixel buffer")?;
             
         // Main event loop
-        let _ = event_loop.run(move |event, _, control_flow| {
+        event_loop.run(move |event, target| {
             match event {
                 Event::WindowEvent { event, .. } => match event {
                     WindowEvent::CloseRequested => {
-                        *control_flow = ControlFlow::Exit;
+                        target.exit();
                     },
                     WindowEvent::Resized(new_size) => {
+                        self.redraw_needed = true;
                         // Resize pixel buffer
                         if pixels.resize_surface(new_size.width, new_size.height).is_err() {
-                            *control_flow = ControlFlow::Exit;
+                            target.exit();
                             return;
                         }
+                        if let Err(e) = self.refit(&mut pixels, new_size.to_logical(window.scale_factor())) {
+                            error!("Failed to fit the grid to the window: {:#}", e);
+                        }
+                    },
+                    WindowEvent::ModifiersChanged(modifiers) => {
+                        self.modifiers = modifiers.state();
+                    },
+                    WindowEvent::CursorMoved { position, .. } => {
+                        self.redraw_needed = true;
+                        self.cursor_cell = self.cell_at(&pixels, (position.x as f32, position.y as f32));
+                        self.paint();
+                    },
+                    WindowEvent::Touch(Touch { phase: TouchPhase::Started, location, .. }) => {
+                        self.redraw_needed = true;
+                        // Goes through the command channel like any other remote toggle; the
+                        // display holds the receiving end, so the send can't fail
+                        let cell = self.cell_at(&pixels, (location.x as f32, location.y as f32));
+                        if let (Some((x, y)), Some((width, height))) = (cell, self.grid_size()) {
+                            let (nx, ny) = ((x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32);
+                            let _ = self.command_sender.send(DisplayCommand::ToggleCell { x: nx, y: ny });
+                        }
+                    },
+                    WindowEvent::CursorLeft { .. } => {
+                        self.cursor_cell = None;
+                        self.redraw_needed = true;
+                    },
+                    WindowEvent::MouseInput { state, button, .. } => {
+                        self.redraw_needed = true;
+                        // Left paints live cells, right erases
+                        self.drawing = match (state, button) {
+                            (ElementState::Pressed, MouseButton::Left) => Some(true),
+                            (ElementState::Pressed, MouseButton::Right) => Some(false),
+                            _ => None,
+                        };
+                        self.paint();
+                    },
+                    WindowEvent::MouseWheel { delta, .. } => {
+                        self.redraw_needed = true;
+                        let steps = match delta {
+                            MouseScrollDelta::LineDelta(_, y) => y,
+                            MouseScrollDelta::PixelDelta(position) => position.y as f32,
+                        };
+                        if steps > 0.0 {
+                            self.adjust_brush(1);
+                        } else if steps < 0.0 {
+                            self.adjust_brush(-1);
+                        }
                     },
-                    WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(key), state: ElementState::Pressed, .. }, .. } => {
-                        self.handle_keyboard_input(key, &window, &mut pixels);
+                    WindowEvent::KeyboardInput { event: KeyEvent { physical_key: PhysicalKey::Code(key), state: ElementState::Pressed, .. }, .. } => {
+                        self.redraw_needed = true;
+                        self.handle_keyboard_input(key, &window);
                     },
                     _ => {},
                 },
-                Event::MainEventsCleared => {
-                    // Check for new audio data
-                    while let Ok(frame) = self.audio_receiver.try_recv() {
-                        self.current_audio_frame = Some(frame);
+                Event::AboutToWait => {
+                    // Stop rather than keep visualizing a dead audio thread
+                    if let Some(error) = self.player.as_ref().and_then(|player| player.take_error()) {
+                        error!("{:#}", error);
+                        target.exit();
+                        return;
                     }
                     
-                    // Update simulation at fixed rate
+                    self.handle_commands();
+                    
+                    // Check for new audio data, released when playback reaches it or,
+                    // without a playback position, after the sync offset
                     let now = Instant::now();
+                    while let Ok(frame) = self.audio_receiver.try_recv() {
+                        // A frame already seen (or older) would replay audio that has passed
+                        if frame.sequence <= self.last_sequence {
+                            debug!("Ignoring repeated audio frame {}", frame.sequence);
+                            continue;
+                        }
+                        if self.last_sequence > 0 && frame.sequence > self.last_sequence + 1 {
+                            debug!("Missed {} audio frames", frame.sequence - self.last_sequence - 1);
+                        }
+                        self.last_sequence = frame.sequence;
+                        if let Some(spectrum) = &frame.spectrum {
+                            if let Err(e) = self.save_spectrum(spectrum) {
+                                error!("Failed to save spectrum: {:#}", e);
+                            }
+                        }
+                        self.pending_frames.push_back((now + self.frame_delay, frame));
+                    }
+                    let playback_time = self.playback_time();
+                    let mut fresh_frame = None;
+                    let mut beats = 0;
+                    let mut treble_onset = false;
+                    while let Some((release_at, frame)) = self.pending_frames.front() {
+                        let due = match playback_time {
+                            Some(time) => frame.time <= time,
+                            None => *release_at <= now,
+                        };
+                        if !due {
+                            break;
+                        }
+                        if let Some((_, frame)) = self.pending_frames.pop_front() {
+                            beats += frame.beat as u32;
+                            treble_onset |= frame.treble_onset;
+                            fresh_frame = Some(frame);
+                        }
+                    }
+                    if self.holding_frame {
+                        // Keep draining so playback stays in sync, but nothing sees the live frames
+                        fresh_frame = None;
+                        beats = 0;
+                        treble_onset = false;
+                    }
+                    let fresh_frame = fresh_frame.map(|frame| frame.with_reactivity(self.config.audio.reactivity));
+                    let fresh_audio = fresh_frame.is_some();
+                    if fresh_audio {
+                        self.current_audio_frame = fresh_frame.clone();
+                    }
+                    // Carry an onset from any frame released since the last step until one uses it
+                    if let (true, Some(frame)) = (treble_onset, self.current_audio_frame.as_mut()) {
+                        frame.treble_onset = true;
+                    }
+                    
+                    // Update simulation at fixed rate, or once per beat
                     let sim_delta = now.duration_since(self.last_sim_update).as_secs_f32();
+                    let step_due = match self.config.simulation.update_trigger {
+                        UpdateTrigger::Timer | UpdateTrigger::Tempo => sim_delta >= 1.0 / self.update_rate(),
+                        UpdateTrigger::Beat => beats > 0,
+                    };
                     
-                    if sim_delta >= 1.0 / self.config.simulation.update_rate {
+                    let mut grid_moved = false;
+                    if !self.paused && step_due {
                         if let Ok(mut sim) = self.simulation.lock() {
                             sim.update(self.current_audio_frame.as_ref());
+                            if let Some(frame) = self.current_audio_frame.as_mut() {
+                                frame.treble_onset = false;
+                            }
+                            trace!("Generation {}: {} live", sim.generation(), sim.population());
                             self.last_sim_update = now;
+                            // Every scheme but Classic colors cells by their age, which a step always advances
+                            grid_moved = sim.changed_last_update() || *self.color_palette.scheme() != ColorScheme::Classic;
                         }
                     }
                     
-                    // Calculate frame time for animations
-                    let frame_delta = now.duration_since(self.last_frame_time).as_secs_f32();
+                    // Calculate frame time for animations, capped so a stalled or
+                    // backgrounded window doesn't jump ahead when it comes back
+                    let frame_delta = now.duration_since(self.last_frame_time).as_secs_f32().min(MAX_FRAME_DELTA);
                     self.last_frame_time = now;
                     
-                    // Update color palette
-                    self.color_palette.update(self.current_audio_frame.as_ref(), frame_delta);
+                    self.advance_auto_cycle(frame_delta, beats);
                     
-                    // Render frame
-                    self.render(pixels.frame_mut());
+                    // Sampled even while hidden, so the graph opens with history to show
+                    if let Ok(sim) = self.simulation.lock() {
+                        self.population_history.push(sim.population() as f32);
+                    }
+                    let energy = self.current_audio_frame.as_ref().map_or(0.0, |frame| frame.overall_energy);
+                    self.energy_history.push(energy);
                     
-                    if pixels.render().is_err() {
-                        *control_flow = ControlFlow::Exit;
-                        return;
+                    // Glide the image sideways, faster with the bass if asked to
+                    let visualization = &self.config.visualization;
+                    if visualization.scroll_speed != 0.0 {
+                        let bass = self.current_audio_frame.as_ref().map_or(0.0, |frame| frame.bass_energy);
+                        let speed = visualization.scroll_speed * (1.0 + visualization.scroll_bass * bass);
+                        let width = self.layout.frame_size.0 as f32;
+                        self.scroll_offset = (self.scroll_offset + speed * frame_delta).rem_euclid(width);
+                    }
+                    
+                    // Ease the zoom toward the level the bass asks for
+                    let pulse = self.config.visualization.zoom_pulse.clamp(0.0, MAX_ZOOM_PULSE);
+                    let bass = self.current_audio_frame.as_ref().map_or(0.0, |frame| frame.bass_energy.clamp(0.0, 1.0));
+                    let follow = 1.0 - (-frame_delta / ZOOM_SMOOTHING_SECS).exp();
+                    self.zoom += (1.0 + pulse * bass - self.zoom) * follow;
+                    
+                    // Every beat restarts the flash, so fast patterns hold it rather than strobe
+                    if beats > 0 && self.config.visualization.beat_flash_ms > 0 {
+                        self.beat_flash_age = Some(0.0);
+                    } else if let Some(age) = self.beat_flash_age.as_mut() {
+                        *age += frame_delta;
+                    }
+                    
+                    // Update color palette; it keeps animating on its own while no frames arrive,
+                    // and only takes (rather than copies) a frame when a new one does
+                    self.color_palette.advance(frame_delta);
+                    if let Some(frame) = fresh_frame {
+                        self.color_palette.set_audio_frame(frame);
+                    }
+                    
+                    // Render frame, unless it would look just like the last one
+                    if self.redraw_needed || grid_moved || self.is_animating(fresh_audio) {
+                        self.render(pixels.frame_mut());
+                        self.redraw_needed = false;
+                        
+                        if pixels.render().is_err() {
+                            target.exit();
+                            return;
+                        }
                     }
                     
                     // Aim for ~60 FPS for the visualization
                     window.request_redraw();
-                    *control_flow = ControlFlow::WaitUntil(
+                    target.set_control_flow(ControlFlow::WaitUntil(
                         Instant::now() + Duration::from_millis(16)
-                    );
+                    ));
                 },
                 _ => {},
             }
-        });
+        }).context("Event loop failed")
     }
     
-    fn render(&self, frame: &mut [u8]) {
-        let background_color = self.color_palette.get_background_color();
+    /// Point in the stream whose frame should be on screen: the playback
+    /// position shifted by the sync offset, if frames follow playback
+    fn playback_time(&self) -> Option<f32> {
+        if !self.config.audio.sync_to_playback {
+            return None;
+        }
+        let position = self.player.as_ref()?.position().as_secs_f32();
+        Some(position - self.config.audio.sync_offset_ms as f32 / 1000.0)
+    }
+    
+    /// Per-channel palettes for split-screen rendering in stereo mode
+    ///
+    /// The left half of the grid reacts to the left channel and the right half
+    /// to the right channel. Empty when the whole grid shares one palette.
+    fn strip_palettes(&self) -> Vec<ColorPalette> {
+        if let (AnalysisMode::Stereo, Some(frame)) = (self.config.audio.analysis_mode, &self.current_audio_frame) {
+            if let (Some(left), Some(right)) = (&frame.left, &frame.right) {
+                return vec![
+                    self.color_palette.with_audio_frame(frame.with_bands(left)),
+                    self.color_palette.with_audio_frame(frame.with_bands(right)),
+                ];
+            }
+        }
+        Vec::new()
+    }
+    
+    fn render(&mut self, frame: &mut [u8]) {
+        // Optionally draw at a multiple of the window resolution and average it back down
+        let scale = self.config.visualization.supersample.max(1) as usize;
+        let tooltip = if scale > 1 {
+            let mut scratch = std::mem::take(&mut self.supersample_buffer);
+            scratch.resize(frame.len() * scale * scale, 0);
+            let tooltip = self.draw_grid(&mut scratch, scale);
+            downsample(&scratch, frame, self.layout.frame_size.0, scale);
+            self.supersample_buffer = scratch;
+            tooltip
+        } else {
+            self.draw_grid(frame, 1)
+        };
+        
+        // Magnify and shift the image; the frame no longer matches the cell cache
+        if self.zoom > 1.0 {
+            draw::zoom(frame, self.layout.frame_size.0, self.zoom);
+            self.cell_cache.invalidate();
+        }
+        if self.scroll_offset != 0.0 {
+            draw::scroll(frame, self.layout.frame_size.0, self.scroll_offset);
+            self.cell_cache.invalidate();
+        }
+        
+        // Dim the whole image during quiet passages; a chroma key must keep its exact color
+        let keyed = matches!(self.config.visualization.background_mode, BackgroundMode::ChromaKey(_));
+        let brightness = draw::brightness(&self.config.visualization, self.current_audio_frame.as_ref());
+        if brightness < 1.0 && !keyed {
+            draw::dim(frame, brightness);
+            self.cell_cache.invalidate();
+        }
+        
+        // Lighten the image on beats, holding each flash for at least beat_flash_ms
+        if let Some(age) = self.beat_flash_age {
+            let hold = self.config.visualization.beat_flash_ms as f32 / 1000.0;
+            let level = draw::beat_flash_level(age, hold);
+            if level > 0.0 && !keyed {
+                draw::flash(frame, level);
+                self.cell_cache.invalidate();
+            } else {
+                self.beat_flash_age = None;
+            }
+        }
+        
+        if let (false, Some(cursor)) = (tooltip.is_empty(), self.cursor_cell) {
+            self.draw_tooltip(frame, cursor, &tooltip);
+            self.cell_cache.invalidate();
+        }
+        
+        if self.show_graph {
+            self.draw_graph(frame);
+            self.cell_cache.invalidate();
+        }
+        
+        if let Some((text, shown_at)) = &self.label {
+            if shown_at.elapsed().as_secs_f32() < LABEL_SECS {
+                self.cell_cache.invalidate();
+                let window_width = self.layout.frame_size.0;
+                let margin = LABEL_SCALE * 2;
+                hud::fill_rect(frame, window_width, 0, 0, hud::text_width(text, LABEL_SCALE) + margin * 2,
+                    hud::line_height(LABEL_SCALE) + margin, Color::new(0, 0, 0, 255));
+                hud::draw_text(frame, window_width, margin, margin, text, Color::white(), LABEL_SCALE);
+            } else {
+                self.label = None;
+            }
+        }
+        
+        // The next frame can't build on premultiplied pixels, so it starts over
+        if self.is_transparent() {
+            draw::premultiply(frame);
+            self.cell_cache.invalidate();
+        }
+    }
+    
+    /// Whether the window lets the desktop show through the background
+    fn is_transparent(&self) -> bool {
+        self.config.visualization.background_mode == BackgroundMode::Transparent
+    }
+    
+    /// Whether the picture changes from frame to frame even while the grid
+    /// stands still, given whether a new audio frame just arrived
+    fn is_animating(&self, fresh_audio: bool) -> bool {
+        let visualization = &self.config.visualization;
+        // Pulse also breathes on its own while the audio is idle
+        let follows_audio = visualization.dim_floor < 1.0
+            || visualization.zoom_pulse > 0.0
+            || visualization.mode.shows_radial();
+        *self.color_palette.scheme() == ColorScheme::Pulse
+            || (fresh_audio && follows_audio)
+            || visualization.trails
+            || (visualization.birth_animation && !self.paused)
+            || visualization.scroll_speed != 0.0
+            || self.zoom > 1.0 + ZOOM_SETTLED
+            || self.beat_flash_age.is_some()
+            || self.label.is_some()
+            || self.show_graph
+    }
+    
+    /// Draw the background, cells, radial spectrum and brush outline into a
+    /// buffer `scale` times the window size, returning the inspection tooltip's lines
+    fn draw_grid(&mut self, frame: &mut [u8], scale: usize) -> Vec<String> {
+        let strip_palettes = self.strip_palettes();
+        let palettes: Vec<&ColorPalette> = if strip_palettes.is_empty() {
+            vec![&self.color_palette]
+        } else {
+            strip_palettes.iter().collect()
+        };
+        let backgrounds: Vec<[u8; 4]> = palettes.iter()
+            .map(|palette| palette.get_background_color().to_rgba())
+            .collect();
+        
+        let mut tooltip = Vec::new();
         
         // Only acquire lock once to minimize contention
         if let Ok(sim) = self.simulation.lock() {
             let width = sim.width();
             let height = sim.height();
-            let cell_size = self.config.visualization.cell_size;
+            let window_width = self.layout.frame_size.0 * scale;
+            
+            // Fraction of the way to the next generation, so births grow smoothly between updates
+            let step_progress = if self.paused {
+                1.0
+            } else {
+                (self.last_sim_update.elapsed().as_secs_f32() * self.update_rate()).min(1.0)
+            };
+            
+            // Plain grids keep what's already in the frame and repaint only changed cells
+            let visualization = &self.config.visualization;
+            let incremental = scale == 1
+                && palettes.len() == 1
+                && self.background_image.is_none()
+                && !visualization.trails
+                && !visualization.birth_animation
+                && visualization.mode == VisualizationMode::Grid;
+            if incremental {
+                self.cell_cache.draw(frame, &self.layout, &sim, palettes[0], visualization.cell_opacity);
+            } else {
+                self.cell_cache.invalidate();
+                
+                // Clear frame with the background, or fade the last frame toward it to leave trails
+                let trails = self.config.visualization.trails && self.trail_buffer.len() == frame.len();
+                let trail_decay = self.config.visualization.trail_decay.clamp(0.0, 1.0);
+                for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+                    let (px, py) = (i % window_width.max(1), i / window_width.max(1));
+                    let background = match &self.background_image {
+                        Some(image) => image.pixel(px, py, scale),
+                        None => backgrounds[self.layout.strip_at(px, scale, palettes.len())],
+                    };
+                    if trails {
+                        let previous = &self.trail_buffer[i * 4..i * 4 + 4];
+                        for ((channel, &from), &to) in pixel.iter_mut().zip(previous).zip(&background) {
+                            // Always move at least one step so trails never get stuck just off the background
+                            let step = (to as f32 - from as f32) * trail_decay;
+                            *channel = (from as f32 + step.signum() * step.abs().ceil()) as u8;
+                        }
+                    } else {
+                        pixel.copy_from_slice(&background);
+                    }
+                }
             
-            // Clear frame with background color
-            for pixel in frame.chunks_exact_mut(4) {
-                pixel.copy_from_slice(&background_color.to_rgba());
+                // Render cells
+                if self.config.visualization.mode.shows_grid() {
+                    draw::draw_cells(frame, scale, &self.layout, &sim, &palettes, &self.config.visualization, step_progress);
+                }
             }
             
-            // Render cells
-            let window_width = self.config.window.width as usize;
+            // Circular spectrum around the center, over the cells in Both mode
+            if let (true, Some(audio_frame)) = (self.config.visualization.mode.shows_radial(), self.current_audio_frame.as_ref()) {
+                draw::draw_spectrum(frame, window_width, audio_frame, &self.color_palette);
+            }
             
-            for y in 0..height {
-                for x in 0..width {
-                    let age = sim.cell_age(x, y);
-                    if age > 0 {
-                        let cell_color = self.color_palette.get_cell_color(age, 255);
-                        
-                        // Draw cell rectangle
-                        for cy in 0..cell_size {
-                            for cx in 0..cell_size {
-                                let px = x * cell_size as usize + cx as usize;
-                                let py = y * cell_size as usize + cy as usize;
-                                
-                                // Check if within window bounds
-                                if px < window_width && py < self.config.window.height as usize {
-                                    let idx = (py * window_width + px) * 4;
-                                    if idx + 3 < frame.len() {
-                                        frame[idx..idx + 4].copy_from_slice(&cell_color.to_rgba());
-                                    }
-                                }
-                            }
-                        }
+            // Keep the composited cells (without the brush outline) for the next frame's trails
+            if self.config.visualization.trails {
+                self.trail_buffer.clear();
+                self.trail_buffer.extend_from_slice(frame);
+            }
+            
+            // Outline the brush under the cursor, leaving live cells visible
+            if let (true, Some((cursor_x, cursor_y))) = (self.config.visualization.mode.shows_grid(), self.cursor_cell) {
+                let r2 = (self.brush_radius * self.brush_radius) as isize;
+                let outside = |dx: isize, dy: isize| dx * dx + dy * dy > r2;
+                for (dx, dy) in gol::disk_offsets(self.brush_radius) {
+                    let on_edge = outside(dx - 1, dy) || outside(dx + 1, dy)
+                        || outside(dx, dy - 1) || outside(dx, dy + 1);
+                    let x = cursor_x as isize + dx;
+                    let y = cursor_y as isize + dy;
+                    if !on_edge || x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+                        continue;
+                    }
+                    if !sim.is_cell_alive(x as usize, y as usize) {
+                        self.fill_cell(frame, scale, x as usize, y as usize, Color::new(96, 96, 96, 255));
+                        self.cell_cache.invalidate_cell(x as usize, y as usize, width);
+                    }
+                }
+            }
+            
+            // Gather the inspected cell's stats while the simulation is locked
+            if let (true, Some((x, y))) = (self.inspecting, self.cursor_cell) {
+                if x < width && y < height {
+                    tooltip = inspect_cell(&sim, x, y);
+                }
+            }
+        }
+        
+        tooltip
+    }
+    
+    /// Show `text` in the top-left corner for a few seconds
+    fn show_label(&mut self, text: String) {
+        self.label = Some((text, Instant::now()));
+    }
+    
+    /// Capture the grid, scheme, rule and runtime tweaks into a slot (1-based) and its file
+    fn save_scene(&mut self, slot: usize) -> Result<()> {
+        let grid = match self.simulation.lock() {
+            Ok(sim) => sim.snapshot(),
+            Err(_) => return Ok(()),
+        };
+        let rule = self.rule_index
+            .map_or_else(|| self.config.simulation.rule.clone(), |index| RULE_PRESETS[index].name.to_string());
+        let state = SceneState {
+            color_scheme: self.color_palette.scheme().clone(),
+            rule,
+            update_rate: self.config.simulation.update_rate,
+            reactivity: self.config.audio.reactivity,
+            trails: self.config.visualization.trails,
+            grid,
+        };
+        
+        state.save(scene::scene_path(slot))?;
+        self.scenes[slot - 1] = Some(state);
+        self.show_label(format!("SAVED SCENE {}", slot));
+        Ok(())
+    }
+    
+    /// Restore a slot (1-based), reading its file if it wasn't saved this session
+    fn load_scene(&mut self, slot: usize) -> Result<()> {
+        if self.scenes[slot - 1].is_none() {
+            let path = scene::scene_path(slot);
+            if !path.exists() {
+                self.show_label(format!("SCENE {} IS EMPTY", slot));
+                return Ok(());
+            }
+            self.scenes[slot - 1] = Some(SceneState::load(path)?);
+        }
+        let state = match &self.scenes[slot - 1] {
+            Some(state) => state.clone(),
+            None => return Ok(()),
+        };
+        
+        let ruleset = rules::resolve_rule(&state.rule)?;
+        if let Ok(mut sim) = self.simulation.lock() {
+            sim.restore(&state.grid)?;
+            sim.set_ruleset(ruleset);
+        }
+        self.rule_index = rules::find_preset(&state.rule);
+        self.config.simulation.rule = state.rule;
+        self.set_scheme(state.color_scheme);
+        self.config.simulation.update_rate = state.update_rate;
+        self.config.audio.reactivity = state.reactivity;
+        if state.trails != self.config.visualization.trails {
+            self.config.visualization.trails = state.trails;
+            self.trail_buffer.clear();
+        }
+        self.last_sim_update = Instant::now();
+        self.remember_start();
+        self.show_label(format!("SCENE {}", slot));
+        Ok(())
+    }
+    
+    /// Take the current grid as the one Backspace goes back to
+    fn remember_start(&mut self) {
+        if let Ok(sim) = self.simulation.lock() {
+            self.start_state = Some(sim.snapshot());
+        }
+    }
+    
+    /// Put back the grid from the last reseed, stamp or scene load
+    fn restart(&mut self) -> Result<()> {
+        if let (Some(state), Ok(mut sim)) = (&self.start_state, self.simulation.lock()) {
+            sim.restore(state)?;
+        }
+        self.last_sim_update = Instant::now();
+        self.show_label("BACK TO START".to_string());
+        Ok(())
+    }
+    
+    /// Stamp the selected library pattern centered on the cursor (or the grid)
+    fn stamp_library_pattern(&mut self) {
+        if let (Some(pattern), Ok(mut sim)) = (self.library.get(self.library_index), self.simulation.lock()) {
+            let (pattern_width, pattern_height) = pattern.size();
+            let (x, y) = self.cursor_cell.unwrap_or((sim.width() / 2, sim.height() / 2));
+            sim.stamp_pattern(
+                &pattern.cells,
+                x.saturating_sub(pattern_width / 2),
+                y.saturating_sub(pattern_height / 2),
+            );
+        }
+        self.remember_start();
+    }
+    
+    /// Pixel of the unzoomed, unscrolled frame shown at a window pixel
+    fn window_to_frame(&self, (px, py): (usize, usize)) -> (usize, usize) {
+        let (width, height) = (self.layout.frame_size.0.max(1), self.layout.frame_size.1.max(1));
+        let px = (px as isize - self.scroll_offset.round() as isize).rem_euclid(width as isize) as usize;
+        let unzoom = |p: usize, size: usize| {
+            let center = size as f32 / 2.0;
+            ((center + (p as f32 + 0.5 - center) / self.zoom).max(0.0) as usize).min(size - 1)
+        };
+        (unzoom(px, width), unzoom(py, height))
+    }
+    
+    /// Window pixel a frame pixel ends up at after zooming and scrolling
+    fn frame_to_window(&self, (x, y): (usize, usize)) -> (usize, usize) {
+        let (width, height) = (self.layout.frame_size.0.max(1), self.layout.frame_size.1.max(1));
+        let zoom = |p: usize, size: usize| {
+            let center = size as f32 / 2.0;
+            ((center + (p as f32 - center) * self.zoom).max(0.0) as usize).min(size - 1)
+        };
+        let x = (zoom(x, width) + self.scroll_offset.round() as usize) % width;
+        (x, zoom(y, height))
+    }
+    
+    /// Plot population and energy over the last few seconds in the bottom-left corner
+    fn draw_graph(&self, frame: &mut [u8]) {
+        let (window_width, window_height) = self.layout.frame_size;
+        let padding = GRAPH_SCALE * 2;
+        let line_height = hud::line_height(GRAPH_SCALE);
+        let box_height = GRAPH_HEIGHT + line_height + padding * 3;
+        let top = window_height.saturating_sub(box_height);
+        let population_color = Color::new(80, 220, 120, 255);
+        let energy_color = Color::new(255, 170, 60, 255);
+        
+        hud::fill_rect(frame, window_width, 0, top, GRAPH_WIDTH + padding * 2, box_height, Color::new(0, 0, 0, 255));
+        let population = format!("POP {}", self.population_history.latest().unwrap_or(0.0));
+        let energy = format!("ENERGY {:.2}", self.energy_history.latest().unwrap_or(0.0));
+        hud::draw_text(frame, window_width, padding, top + padding, &population, population_color, GRAPH_SCALE);
+        let energy_left = padding * 3 + hud::text_width(&population, GRAPH_SCALE);
+        hud::draw_text(frame, window_width, energy_left, top + padding, &energy, energy_color, GRAPH_SCALE);
+        
+        // Each line fits its own range, so both use the full height
+        let plot_top = top + padding * 2 + line_height;
+        for (history, color) in [(&self.population_history, population_color), (&self.energy_history, energy_color)] {
+            hud::draw_plot(frame, window_width, (padding, plot_top), (GRAPH_WIDTH, GRAPH_HEIGHT), history, color);
+        }
+    }
+    
+    /// Draw `lines` in a box beside the cursor cell, kept inside the window
+    fn draw_tooltip(&self, frame: &mut [u8], (x, y): (usize, usize), lines: &[String]) {
+        let Layout { frame_size: (window_width, window_height), cell_size, grid_offset } = self.layout;
+        let (cell_left, cell_top) = self.frame_to_window((grid_offset.0 + x * cell_size, grid_offset.1 + y * cell_size));
+        let padding = TOOLTIP_SCALE * 2;
+        let box_width = lines.iter().map(|line| hud::text_width(line, TOOLTIP_SCALE)).max().unwrap_or(0) + padding * 2;
+        let box_height = lines.len() * hud::line_height(TOOLTIP_SCALE) + padding * 2;
+        
+        // Prefer below-right of the cursor, flipping to the other side near the edges
+        let mut left = cell_left + cell_size + padding;
+        if left + box_width > window_width {
+            left = cell_left.saturating_sub(box_width + padding);
+        }
+        let mut top = cell_top + cell_size + padding;
+        if top + box_height > window_height {
+            top = cell_top.saturating_sub(box_height + padding);
+        }
+        
+        hud::fill_rect(frame, window_width, left, top, box_width, box_height, Color::new(0, 0, 0, 255));
+        for (i, line) in lines.iter().enumerate() {
+            let line_top = top + padding + i * hud::line_height(TOOLTIP_SCALE);
+            hud::draw_text(frame, window_width, left + padding, line_top, line, Color::white(), TOOLTIP_SCALE);
+        }
+    }
+    
+    /// Generations per second: the configured rate, or derived from the tempo in Tempo mode
+    fn update_rate(&self) -> f32 {
+        let simulation = &self.config.simulation;
+        let bpm = self.current_audio_frame.as_ref().and_then(|frame| frame.estimated_bpm);
+        match (simulation.update_trigger, bpm) {
+            (UpdateTrigger::Tempo, Some(bpm)) => {
+                (bpm / 60.0 * simulation.updates_per_beat).clamp(MIN_TEMPO_UPDATE_RATE, MAX_TEMPO_UPDATE_RATE)
+            },
+            _ => simulation.update_rate,
+        }
+    }
+    
+    /// Fill the pixels of one grid cell, clipped to the window
+    fn fill_cell(&self, frame: &mut [u8], scale: usize, x: usize, y: usize, color: Color) {
+        self.layout.fill_cell(frame, scale, x, y, color, 0);
+    }
+    
+    /// Move to the next color scheme once the auto-cycle interval has passed
+    fn advance_auto_cycle(&mut self, frame_delta: f32, beats: u32) {
+        let due = match self.config.visualization.auto_cycle {
+            Some(AutoCycle::TimeInterval(secs)) => {
+                self.cycle_elapsed += frame_delta;
+                self.cycle_elapsed >= secs
+            },
+            Some(AutoCycle::EveryNBeats(count)) => {
+                self.cycle_beats += beats;
+                self.cycle_beats >= count.max(1)
+            },
+            None => false,
+        };
+        if due {
+            self.cycle_elapsed = 0.0;
+            self.cycle_beats = 0;
+            let next = self.color_palette.scheme().next();
+            self.change_scheme(next, false);
+        }
+    }
+    
+    /// Borderless fullscreen on the chosen monitor, clamped to those available
+    fn fullscreen_mode(&self, window: &winit::window::Window) -> Fullscreen {
+        let monitor = self.config.window.monitor.and_then(|index| {
+            let count = window.available_monitors().count();
+            window.available_monitors().nth(index.min(count.saturating_sub(1)))
+        });
+        Fullscreen::Borderless(monitor)
+    }
+    
+    /// Grid cell under a position in window coordinates, if it is over the grid
+    /// rather than the letterbox margins around it
+    fn cell_at(&self, pixels: &Pixels, position: (f32, f32)) -> Option<(usize, usize)> {
+        let (cell_size, (offset_x, offset_y)) = (self.layout.cell_size, self.layout.grid_offset);
+        let (width, height) = self.grid_size()?;
+        pixels
+            .window_pos_to_pixel(position)
+            .ok()
+            .map(|pixel| self.window_to_frame(pixel))
+            .and_then(|(px, py)| Some((px.checked_sub(offset_x)?, py.checked_sub(offset_y)?)))
+            .map(|(px, py)| (px / cell_size, py / cell_size))
+            .filter(|&(x, y)| x < width && y < height)
+    }
+    
+    /// Width and height of the simulation in cells
+    fn grid_size(&self) -> Option<(usize, usize)> {
+        self.simulation.lock().ok().map(|sim| (sim.width(), sim.height()))
+    }
+    
+    /// Handle for controlling the display from another thread once it runs
+    pub fn command_sender(&self) -> Sender<DisplayCommand> {
+        self.command_sender.clone()
+    }
+    
+    /// Forward a request to the analysis thread, which is gone once playback has failed
+    fn send_to_analyzer(&self, command: AnalyzerCommand) {
+        if let Err(unsent) = self.analyzer_commands.send(command) {
+            debug!("Analyzer isn't running; dropped {:?}", unsent.0);
+        }
+    }
+    
+    /// Apply any requests sent through `command_sender`
+    fn handle_commands(&mut self) {
+        while let Ok(command) = self.commands.try_recv() {
+            match command {
+                DisplayCommand::ToggleCell { x, y } => {
+                    if let Ok(mut sim) = self.simulation.lock() {
+                        sim.toggle_cell_normalized(x, y);
                     }
+                },
+            }
+            self.redraw_needed = true;
+        }
+    }
+    
+    /// Paint the brush at the cursor while a mouse button is held
+    fn paint(&mut self) {
+        if let (Some(alive), Some((x, y))) = (self.drawing, self.cursor_cell) {
+            if let Ok(mut sim) = self.simulation.lock() {
+                for (px, py) in self.symmetry.partners(x, y, sim.width(), sim.height()) {
+                    sim.set_cells_in_radius(px, py, self.brush_radius, alive);
                 }
             }
         }
     }
     
-    fn handle_keyboard_input(&mut self, key: VirtualKeyCode, window: &winit::window::Window, pixels: &mut Pixels) {
+    /// Write the bounding box of the live cells to a timestamped .rle or .cells file
+    fn export_pattern(&self, plaintext: bool) -> Result<()> {
+        let (text, extension) = match self.simulation.lock() {
+            Ok(sim) => match sim.live_bounds() {
+                Some(bounds) if plaintext => (sim.to_pattern(Some(bounds)).to_cells(), "cells"),
+                Some(bounds) => (sim.to_rle(Some(bounds)), "rle"),
+                None => {
+                    warn!("No live cells to export");
+                    return Ok(());
+                },
+            },
+            Err(_) => return Ok(()),
+        };
+        
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let path = format!("pattern-{}.{}", timestamp, extension);
+        fs::write(&path, text).with_context(|| format!("Failed to write {}", path))?;
+        info!("Exported pattern to {}", path);
+        Ok(())
+    }
+    
+    /// Write the effective config, including every runtime tweak, to a timestamped TOML file
+    fn dump_config(&self) -> Result<()> {
+        let text = toml::to_string(&self.config).context("Failed to serialize config")?;
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let path = format!("config-{}.toml", timestamp);
+        fs::write(&path, text).with_context(|| format!("Failed to write {}", path))?;
+        info!("Saved config to {}", path);
+        Ok(())
+    }
+    
+    /// Write a captured spectrum to a timestamped CSV file
+    fn save_spectrum(&self, spectrum: &Spectrum) -> Result<()> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let path = format!("spectrum-{}.csv", timestamp);
+        fs::write(&path, spectrum.to_csv()).with_context(|| format!("Failed to write {}", path))?;
+        info!("Saved spectrum to {}", path);
+        Ok(())
+    }
+    
+    /// Switch color scheme, keeping the config in step
+    fn set_scheme(&mut self, scheme: ColorScheme) {
+        info!("Color scheme: {}", scheme.name());
+        self.color_palette.set_scheme(scheme.clone());
+        self.config.visualization.color_scheme = scheme;
+        self.redraw_needed = true;
+    }
+    
+    /// Switch color scheme as the player asked, restarting the cell ages when
+    /// `reset_ages` is given or `reset_ages_on_scheme_change` is set
+    fn change_scheme(&mut self, scheme: ColorScheme, reset_ages: bool) {
+        self.set_scheme(scheme);
+        if reset_ages || self.config.visualization.reset_ages_on_scheme_change {
+            if let Ok(mut sim) = self.simulation.lock() {
+                sim.reset_ages();
+            }
+            self.cell_cache.invalidate();
+        }
+    }
+    
+    /// Grow or shrink the drawing brush
+    fn adjust_brush(&mut self, delta: isize) {
+        let radius = (self.brush_radius as isize + delta).clamp(0, MAX_BRUSH_RADIUS as isize) as usize;
+        if radius != self.brush_radius {
+            self.brush_radius = radius;
+            debug!("Brush radius: {}", radius);
+        }
+    }
+    
+    fn handle_keyboard_input(&mut self, key: KeyCode, window: &winit::window::Window) {
+        // Shift+digit saves a scene, Ctrl+digit recalls it
+        if let Some(slot) = scene_slot(key) {
+            if self.modifiers.shift_key() {
+                if let Err(e) = self.save_scene(slot) {
+                    error!("Failed to save scene {}: {:#}", slot, e);
+                }
+                return;
+            }
+            if self.modifiers.control_key() {
+                if let Err(e) = self.load_scene(slot) {
+                    error!("Failed to load scene {}: {:#}", slot, e);
+                }
+                return;
+            }
+        }
+        
         match key {
-            VirtualKeyCode::Escape => {
+            KeyCode::Escape => {
                 // Toggle fullscreen
                 let is_fullscreen = window.fullscreen().is_some();
                 window.set_fullscreen(if is_fullscreen {
                     None
                 } else {
-                    Some(Fullscreen::Borderless(None))
+                    Some(self.fullscreen_mode(window))
                 });
             },
-            VirtualKeyCode::Space => {
+            KeyCode::Tab if window.fullscreen().is_some() => {
+                // Move fullscreen to the next monitor
+                let count = window.available_monitors().count();
+                if count > 0 {
+                    let current = self.config.window.monitor.or_else(|| {
+                        let current = window.current_monitor()?;
+                        window.available_monitors().position(|monitor| monitor == current)
+                    });
+                    self.config.window.monitor = Some(current.map_or(0, |index| (index + 1) % count));
+                    window.set_fullscreen(Some(self.fullscreen_mode(window)));
+                }
+            },
+            KeyCode::Space => {
                 // Reset simulation with random state
                 if let Ok(mut sim) = self.simulation.lock() {
                     sim.randomize(self.config.simulation.initial_seed);
                 }
+                self.remember_start();
+            },
+            KeyCode::Backspace => {
+                // Go back to how the grid looked after the last reseed, stamp or scene load
+                if let Err(e) = self.restart() {
+                    error!("Failed to restart: {:#}", e);
+                }
             },
-            VirtualKeyCode::C => {
+            KeyCode::ArrowUp | KeyCode::ArrowDown if self.modifiers.shift_key() => {
+                // Adjust the seeding density and reseed so the change shows at once
+                let step = if key == KeyCode::ArrowUp { DENSITY_STEP } else { -DENSITY_STEP };
+                let density = (self.config.simulation.initial_seed + step).clamp(MIN_DENSITY, MAX_DENSITY);
+                self.config.simulation.initial_seed = density;
+                if let Ok(mut sim) = self.simulation.lock() {
+                    sim.randomize(density);
+                }
+                self.remember_start();
+                self.show_label(format!("DENSITY {:.0}%", density * 100.0));
+            },
+            KeyCode::KeyC => {
                 // Clear simulation
                 if let Ok(mut sim) = self.simulation.lock() {
                     sim.clear();
                 }
             },
-            VirtualKeyCode::Key1 => {
-                // Switch to Classic color scheme
-                self.color_palette.set_scheme(ColorScheme::Classic);
+            KeyCode::KeyR => {
+                // Cycle through the built-in rules, skipping Generations rules unless enabled
+                let needs_generations = |index: usize| {
+                    rules::resolve_rule(RULE_PRESETS[index].name).is_ok_and(|rule| rule.states() > 2)
+                };
+                let mut next = self.rule_index.map_or(0, |i| (i + 1) % RULE_PRESETS.len());
+                while !self.config.simulation.generations && needs_generations(next) {
+                    next = (next + 1) % RULE_PRESETS.len();
+                }
+                self.rule_index = Some(next);
+                
+                let preset = &RULE_PRESETS[next];
+                self.config.simulation.rule = preset.name.to_string();
+                if let (Ok(ruleset), Ok(mut sim)) = (rules::resolve_rule(preset.name), self.simulation.lock()) {
+                    sim.set_ruleset(ruleset);
+                    info!("Rule: {} ({})", preset.name, preset.notation);
+                }
+            },
+            KeyCode::BracketLeft => {
+                // Shrink the drawing brush
+                self.adjust_brush(-1);
+            },
+            KeyCode::BracketRight => {
+                // Grow the drawing brush
+                self.adjust_brush(1);
+            },
+            KeyCode::KeyS if self.modifiers.shift_key() => {
+                // Mirror the current grid with the active symmetry
+                if let Ok(mut sim) = self.simulation.lock() {
+                    sim.symmetrize(self.symmetry);
+                }
+            },
+            KeyCode::KeyS => {
+                // Cycle the drawing symmetry
+                self.symmetry = self.symmetry.next();
+                info!("Symmetry: {:?}", self.symmetry);
+            },
+            KeyCode::KeyE => {
+                // Export the live cells as RLE, or as plaintext .cells with Shift
+                if let Err(e) = self.export_pattern(self.modifiers.shift_key()) {
+                    error!("Failed to export pattern: {:#}", e);
+                }
+            },
+            KeyCode::KeyL if !self.library.is_empty() => {
+                // Select the next pattern in the library, or the previous with Shift
+                let count = self.library.len();
+                self.library_index = if self.modifiers.shift_key() {
+                    (self.library_index + count - 1) % count
+                } else {
+                    (self.library_index + 1) % count
+                };
+                let name = self.library[self.library_index].name.clone().unwrap_or_default();
+                self.show_label(format!("PATTERN: {}", name));
+            },
+            KeyCode::Enter => {
+                // Stamp the selected library pattern at the cursor
+                self.stamp_library_pattern();
+            },
+            KeyCode::KeyP => {
+                // Pause or resume the simulation together with playback
+                self.paused = !self.paused;
+                if let Some(player) = self.player.as_mut() {
+                    if self.paused {
+                        player.pause();
+                    } else {
+                        player.resume();
+                    }
+                }
+                // Stop analysis too, so no stale frames keep nudging the grid
+                self.send_to_analyzer(AnalyzerCommand::SetPaused(self.paused));
+                if !self.paused {
+                    // Time spent paused doesn't count toward the next generation
+                    self.last_sim_update = Instant::now();
+                }
+                info!("{}", if self.paused { "Paused" } else { "Resumed" });
+            },
+            KeyCode::KeyM => {
+                // Toggle between mono mixdown and split left/right analysis
+                let mode = self.config.audio.analysis_mode.toggled();
+                self.config.audio.analysis_mode = mode;
+                self.send_to_analyzer(AnalyzerCommand::SetMode(mode));
+            },
+            KeyCode::Comma | KeyCode::Period => {
+                // Adjust how strongly the visuals react to the audio
+                let step = if key == KeyCode::Period { REACTIVITY_STEP } else { 1.0 / REACTIVITY_STEP };
+                let audio = &mut self.config.audio;
+                audio.reactivity = (audio.reactivity * step).clamp(MIN_REACTIVITY, MAX_REACTIVITY);
+                info!("Reactivity: {:.2}", audio.reactivity);
+            },
+            KeyCode::KeyG => {
+                // Toggle motion trails, starting them from a clean frame
+                let visualization = &mut self.config.visualization;
+                visualization.trails = !visualization.trails;
+                self.trail_buffer.clear();
+                info!("Trails: {}", if visualization.trails { "on" } else { "off" });
+            },
+            KeyCode::KeyA => {
+                // Toggle between audio-warped rules and the plain base rule
+                let enabled = !self.config.simulation.audio_affects_rules;
+                self.config.simulation.audio_affects_rules = enabled;
+                if let Ok(mut sim) = self.simulation.lock() {
+                    sim.set_audio_affects_rules(enabled);
+                }
+                self.show_label(format!("RULES: {}", if enabled { "AUDIO" } else { "CLASSIC" }));
+            },
+            KeyCode::KeyD => {
+                // Save the current settings, tweaks included, as a config file
+                if let Err(e) = self.dump_config() {
+                    error!("Failed to save config: {:#}", e);
+                }
+            },
+            KeyCode::KeyF if self.modifiers.shift_key() => {
+                // Hold the current audio frame, so colors can be tuned against a fixed
+                // reactive state, or go back to live frames
+                self.holding_frame = !self.holding_frame;
+                if let (true, Some(frame)) = (self.holding_frame, &self.current_audio_frame) {
+                    info!(
+                        "Holding audio frame {}: bass {:.2}, mid {:.2}, treble {:.2}, overall {:.2}",
+                        frame.sequence, frame.bass_energy, frame.mid_energy, frame.treble_energy, frame.overall_energy,
+                    );
+                    // Latch it undecayed, and keep it from settling while held
+                    self.color_palette.set_audio_frame(frame.clone());
+                }
+                let idle_decay = if self.holding_frame { 0.0 } else { self.config.visualization.idle_decay };
+                self.color_palette.set_idle_decay(idle_decay);
+                self.redraw_needed = true;
+                self.show_label(format!("FRAME: {}", if self.holding_frame { "HELD" } else { "LIVE" }));
+            },
+            KeyCode::KeyF => {
+                // Save the spectrum of the next analyzed window as CSV
+                self.send_to_analyzer(AnalyzerCommand::CaptureSpectrum);
+            },
+            KeyCode::Minus | KeyCode::Equal => {
+                // Adjust the playback volume
+                let step = if key == KeyCode::Equal { VOLUME_STEP } else { -VOLUME_STEP };
+                let volume = (self.config.audio.volume + step).clamp(0.0, MAX_VOLUME);
+                self.config.audio.volume = volume;
+                if let Some(player) = self.player.as_mut() {
+                    player.set_volume(volume);
+                }
+                self.show_label(format!("VOLUME {:.0}%", volume * 100.0));
+            },
+            KeyCode::KeyV => {
+                // Cycle between the cells, the radial spectrum and both
+                let mode = self.config.visualization.mode.next();
+                self.config.visualization.mode = mode;
+                self.show_label(format!("MODE: {:?}", mode));
+            },
+            KeyCode::KeyI => {
+                // Toggle the cell inspection tooltip
+                self.inspecting = !self.inspecting;
+            },
+            KeyCode::KeyH => {
+                // Toggle the population and energy graph
+                self.show_graph = !self.show_graph;
             },
-            VirtualKeyCode::Key2 => {
+            KeyCode::KeyT => {
+                // Toggle automatic color scheme cycling
+                let auto_cycle = &mut self.config.visualization.auto_cycle;
+                *auto_cycle = match auto_cycle {
+                    Some(_) => None,
+                    None => Some(self.cycle_interval),
+                };
+                self.cycle_elapsed = 0.0;
+                self.cycle_beats = 0;
+                info!("Color scheme cycling: {}", if auto_cycle.is_some() { "on" } else { "off" });
+            },
+            KeyCode::Digit1 => {
+                // Switch to Classic color scheme, restarting cell ages with Alt
+                self.change_scheme(ColorScheme::Classic, self.modifiers.alt_key());
+            },
+            KeyCode::Digit2 => {
                 // Switch to Heat color scheme
-                self.color_palette.set_scheme(ColorScheme::Heat);
+                self.change_scheme(ColorScheme::Heat, self.modifiers.alt_key());
             },
-            VirtualKeyCode::Key3 => {
+            KeyCode::Digit3 => {
                 // Switch to Rainbow color scheme
-                self.color_palette.set_scheme(ColorScheme::Rainbow);
+                self.change_scheme(ColorScheme::Rainbow, self.modifiers.alt_key());
             },
-            VirtualKeyCode::Key4 => {
+            KeyCode::Digit4 => {
                 // Switch to Pulse color scheme
-                self.color_palette.set_scheme(ColorScheme::Pulse);
+                self.change_scheme(ColorScheme::Pulse, self.modifiers.alt_key());
+            },
+            KeyCode::Digit5 => {
+                // Switch to Stepped color scheme
+                self.change_scheme(ColorScheme::Stepped, self.modifiers.alt_key());
             },
             _ => {},
         }
     }
-}
\ No newline at end of file
+}
+
+/// Scene slot (1-based) bound to a digit key
+fn scene_slot(key: KeyCode) -> Option<usize> {
+    let slot = match key {
+        KeyCode::Digit1 => 1,
+        KeyCode::Digit2 => 2,
+        KeyCode::Digit3 => 3,
+        KeyCode::Digit4 => 4,
+        KeyCode::Digit5 => 5,
+        KeyCode::Digit6 => 6,
+        KeyCode::Digit7 => 7,
+        KeyCode::Digit8 => 8,
+        KeyCode::Digit9 => 9,
+        _ => return None,
+    };
+    Some(slot)
+}
+
+/// Describe a cell's state, age, neighbors and the rule's verdict for the inspection tooltip
+fn inspect_cell(sim: &GameOfLife, x: usize, y: usize) -> Vec<String> {
+    let alive = sim.is_cell_alive(x, y);
+    let state = match sim.cell_state(x, y) {
+        0 => "DEAD".to_string(),
+        1 => format!("ALIVE AGE {}", sim.cell_age(x, y)),
+        dying => format!("DYING {}/{}", dying - 1, sim.state_count() - 2),
+    };
+    let verdict = match (alive, sim.rule_decision(x, y)) {
+        _ if sim.cell_state(x, y) > 1 => "KEEPS DYING",
+        (true, true) => "SURVIVES",
+        (true, false) => "DIES",
+        (false, true) => "BORN",
+        (false, false) => "STAYS DEAD",
+    };
+    
+    vec![
+        format!("CELL {},{}", x, y),
+        state,
+        format!("NEIGHBORS {}", sim.cell_neighbors(x, y)),
+        format!("RULE: {}", verdict),
+    ]
+}
+
+/// Box-filter a buffer `scale` times the size of `frame` down into it
+fn downsample(source: &[u8], frame: &mut [u8], width: usize, scale: usize) {
+    let source_width = width * scale;
+    let samples = (scale * scale) as u32;
+    for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+        let (x, y) = (i % width, i / width);
+        let mut sum = [0u32; 4];
+        for sy in y * scale..(y + 1) * scale {
+            let row = (sy * source_width + x * scale) * 4;
+            for source_pixel in source[row..row + scale * 4].chunks_exact(4) {
+                for (total, &value) in sum.iter_mut().zip(source_pixel) {
+                    *total += value as u32;
+                }
+            }
+        }
+        for (channel, total) in pixel.iter_mut().zip(sum) {
+            *channel = ((total + samples / 2) / samples) as u8;
+        }
+    }
+}
@@ -0,0 +1,481 @@
+use anyhow::{Context, Result};
+use image::imageops::FilterType;
+use std::path::Path;
+
+use crate::audio::analyzer::AudioFrame;
+use crate::config::{Config, VisualizationConfig};
+use crate::renderer::color::{Color, ColorPalette};
+use crate::renderer::radial::{self, RADIAL_BARS};
+use crate::simulation::gol::{GameOfLife, GridSnapshot};
+
+/// Generations a newborn cell takes to grow to full size with `birth_animation`
+const BIRTH_GENERATIONS: f32 = 3.0;
+
+/// How far a beat flash lightens the frame toward white at its peak, and how
+/// long it takes to fade once its hold is over
+const BEAT_FLASH_STRENGTH: f32 = 0.3;
+const BEAT_FLASH_FADE_SECS: f32 = 0.15;
+
+/// Where the grid sits in the frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub frame_size: (usize, usize),  // Size of the pixel buffer (and window)
+    pub cell_size: usize,            // Pixels per cell, configured or fitted to the window
+    pub grid_offset: (usize, usize), // Letterbox margin left and above the grid, in pixels
+}
+
+impl Layout {
+    /// Window size, cell size and letterbox offset for a grid of `grid_size` cells
+    ///
+    /// Without a configured `cell_size` the largest size that fits the window is
+    /// used and the grid is centered. With `size_from_grid` the window is sized
+    /// to the grid instead.
+    pub fn new(config: &Config, (grid_width, grid_height): (usize, usize)) -> Self {
+        let window = (config.window.width as usize, config.window.height as usize);
+        let (frame_size, cell_size) = match (config.visualization.cell_size, config.window.size_from_grid) {
+            (Some(size), true) => ((grid_width * size as usize, grid_height * size as usize), size as usize),
+            (Some(size), false) => (window, size as usize),
+            (None, _) => {
+                let fit = (window.0 / grid_width.max(1)).min(window.1 / grid_height.max(1)).max(1);
+                (window, fit)
+            },
+        };
+        let frame_size = (frame_size.0.max(1), frame_size.1.max(1));
+        let grid_offset = (
+            frame_size.0.saturating_sub(grid_width * cell_size) / 2,
+            frame_size.1.saturating_sub(grid_height * cell_size) / 2,
+        );
+        Layout { frame_size, cell_size: cell_size.max(1), grid_offset }
+    }
+    
+    /// Which of `strips` side-by-side palettes a pixel column belongs to, in a
+    /// buffer `scale` times the frame size
+    pub fn strip_at(&self, px: usize, scale: usize, strips: usize) -> usize {
+        (px * strips / (self.frame_size.0 * scale).max(1)).min(strips.saturating_sub(1))
+    }
+    
+    /// Fill a cell leaving `inset` pixels of margin on every side, in a buffer
+    /// `scale` times the frame size, blending by the color's alpha
+    pub fn fill_cell(&self, frame: &mut [u8], scale: usize, x: usize, y: usize, color: Color, inset: usize) {
+        let cell_size = self.cell_size * scale;
+        let window_width = self.frame_size.0 * scale;
+        let window_height = self.frame_size.1 * scale;
+        let (offset_x, offset_y) = (self.grid_offset.0 * scale, self.grid_offset.1 * scale);
+        
+        for cy in inset..cell_size.saturating_sub(inset) {
+            for cx in inset..cell_size.saturating_sub(inset) {
+                let px = offset_x + x * cell_size + cx;
+                let py = offset_y + y * cell_size + cy;
+                
+                // Check if within window bounds
+                if px < window_width && py < window_height {
+                    let idx = (py * window_width + px) * 4;
+                    if idx + 3 < frame.len() {
+                        blend_pixel(&mut frame[idx..idx + 4], color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Composite `color` over an opaque pixel by its alpha, leaving the pixel opaque
+pub fn blend_pixel(pixel: &mut [u8], color: Color) {
+    if color.a == 255 {
+        pixel.copy_from_slice(&color.to_rgba());
+        return;
+    }
+    let alpha = color.a as f32 / 255.0;
+    if pixel[3] == 255 {
+        for (channel, target) in pixel[..3].iter_mut().zip([color.r, color.g, color.b]) {
+            *channel = (*channel as f32 + (target as f32 - *channel as f32) * alpha).round() as u8;
+        }
+        return;
+    }
+    
+    // Over a (partly) transparent background the result keeps some transparency
+    let below = pixel[3] as f32 / 255.0 * (1.0 - alpha);
+    let coverage = alpha + below;
+    if coverage <= 0.0 {
+        return;
+    }
+    for (channel, target) in pixel[..3].iter_mut().zip([color.r, color.g, color.b]) {
+        *channel = ((target as f32 * alpha + *channel as f32 * below) / coverage).round() as u8;
+    }
+    pixel[3] = (coverage * 255.0).round() as u8;
+}
+
+/// Scale every pixel's color by its alpha, for windows that composite
+/// premultiplied pixels over the desktop
+pub fn premultiply(frame: &mut [u8]) {
+    for pixel in frame.chunks_exact_mut(4) {
+        let alpha = pixel[3] as f32 / 255.0;
+        for channel in &mut pixel[..3] {
+            *channel = (*channel as f32 * alpha).round() as u8;
+        }
+    }
+}
+
+/// Picture shown behind the cells in place of the scheme's background color
+pub struct BackgroundImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>, // RGBA at the frame size
+}
+
+impl BackgroundImage {
+    /// Load an image and scale it to cover a frame of `frame_size`, cropping
+    /// whatever overhangs so the picture keeps its proportions
+    pub fn load<P: AsRef<Path>>(path: P, (width, height): (usize, usize)) -> Result<Self> {
+        let image = image::open(path.as_ref())
+            .with_context(|| format!("Failed to load background image {}", path.as_ref().display()))?;
+        let pixels = image
+            .resize_to_fill(width as u32, height as u32, FilterType::Triangle)
+            .to_rgba8()
+            .into_raw();
+        Ok(BackgroundImage { width, height, pixels })
+    }
+    
+    /// Color under a pixel of a buffer `scale` times the frame size
+    pub fn pixel(&self, px: usize, py: usize, scale: usize) -> [u8; 4] {
+        let x = (px / scale.max(1)).min(self.width.saturating_sub(1));
+        let y = (py / scale.max(1)).min(self.height.saturating_sub(1));
+        let idx = (y * self.width + x) * 4;
+        match self.pixels.get(idx..idx + 4) {
+            Some(rgba) => [rgba[0], rgba[1], rgba[2], 255],
+            None => [0, 0, 0, 255],
+        }
+    }
+}
+
+/// Clear the frame to the background image, or to each strip's background color
+pub fn fill_background(frame: &mut [u8], scale: usize, layout: &Layout, palettes: &[&ColorPalette], image: Option<&BackgroundImage>) {
+    let width = (layout.frame_size.0 * scale).max(1);
+    let backgrounds: Vec<[u8; 4]> = palettes.iter()
+        .map(|palette| palette.get_background_color().to_rgba())
+        .collect();
+    for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+        let background = match image {
+            Some(image) => image.pixel(i % width, i / width, scale),
+            None => backgrounds[layout.strip_at(i % width, scale, palettes.len())],
+        };
+        pixel.copy_from_slice(&background);
+    }
+}
+
+/// Draw the live and dying cells, each in the palette of the strip it falls in,
+/// blended over whatever is already in the frame at `cell_opacity`
+///
+/// `step_progress` is how far the simulation is toward its next generation,
+/// which newborn cells use to grow smoothly with `birth_animation`.
+pub fn draw_cells(
+    frame: &mut [u8],
+    scale: usize,
+    layout: &Layout,
+    sim: &GameOfLife,
+    palettes: &[&ColorPalette],
+    visualization: &VisualizationConfig,
+    step_progress: f32,
+) {
+    let cell_size = layout.cell_size * scale;
+    let offset_x = layout.grid_offset.0 * scale;
+    let states = sim.state_count();
+    let opacity = visualization.cell_opacity.clamp(0.0, 1.0);
+    
+    for y in 0..sim.height() {
+        for x in 0..sim.width() {
+            let age = sim.cell_age(x, y);
+            let palette = palettes[layout.strip_at(offset_x + x * cell_size, scale, palettes.len())];
+            if age > 0 {
+                let cell_color = palette.get_cell_color(age, 255).fade(opacity);
+                let growth = (age as f32 - 1.0 + step_progress) / BIRTH_GENERATIONS;
+                if visualization.birth_animation && growth < 1.0 {
+                    // Young cells start small and faint, blooming to full size
+                    let inset = ((1.0 - growth) * cell_size as f32 / 2.0) as usize;
+                    layout.fill_cell(frame, scale, x, y, cell_color.fade(growth), inset);
+                } else {
+                    layout.fill_cell(frame, scale, x, y, cell_color, 0);
+                }
+            } else if states > 2 {
+                // Dying cells fade from the newborn color to transparent
+                let state = sim.cell_state(x, y);
+                if state > 1 {
+                    let fade = (state - 1) as f32 / (states - 1) as f32;
+                    let cell_color = palette.get_cell_color(1, 255).fade(opacity * (1.0 - fade));
+                    layout.fill_cell(frame, scale, x, y, cell_color, 0);
+                }
+            }
+        }
+    }
+}
+
+/// What a persistent frame buffer already shows, so a mostly static grid is
+/// redrawn by repainting only the cells whose color changed
+///
+/// Covers the plain case: a single palette at the window's resolution with
+/// solid background and no trails or birth animation. Anything else drawn
+/// into the frame must `invalidate` what it covered.
+#[derive(Debug, Default)]
+pub struct CellCache {
+    layout: Option<Layout>,  // Layout the frame was last fully painted with
+    background: [u8; 4],     // Background color it was painted with
+    colors: Vec<[u8; 4]>,    // Color each cell was last painted, background included
+    pixels_written: usize,   // Pixels painted by the last draw
+}
+
+impl CellCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Forget what the frame shows, so the next draw repaints all of it
+    pub fn invalidate(&mut self) {
+        self.layout = None;
+    }
+    
+    /// Forget a single cell, e.g. after drawing an outline over it
+    pub fn invalidate_cell(&mut self, x: usize, y: usize, grid_width: usize) {
+        if let Some(color) = self.colors.get_mut(y * grid_width + x) {
+            // Alpha 0 is never painted, so the cell won't match next time
+            *color = [0; 4];
+        }
+    }
+    
+    /// Pixels painted by the last `draw`, for profiling
+    pub fn pixels_written(&self) -> usize {
+        self.pixels_written
+    }
+    
+    /// Bring `frame` up to date with the simulation, painting the whole frame
+    /// only when the layout or background changed since the last draw
+    pub fn draw(&mut self, frame: &mut [u8], layout: &Layout, sim: &GameOfLife, palette: &ColorPalette, cell_opacity: f32) {
+        let background = palette.get_background_color().to_rgba();
+        let cells = sim.width() * sim.height();
+        self.pixels_written = 0;
+        if self.layout != Some(*layout) || self.background != background || self.colors.len() != cells {
+            fill_background(frame, 1, layout, &[palette], None);
+            self.pixels_written += frame.len() / 4;
+            self.layout = Some(*layout);
+            self.background = background;
+            self.colors.clear();
+            self.colors.resize(cells, background);
+        }
+        
+        let opacity = cell_opacity.clamp(0.0, 1.0);
+        let states = sim.state_count();
+        let cell_pixels = layout.cell_size * layout.cell_size;
+        for y in 0..sim.height() {
+            for x in 0..sim.width() {
+                // Same colors draw_cells would composite over the background
+                let age = sim.cell_age(x, y);
+                let state = sim.cell_state(x, y);
+                let cell_color = if age > 0 {
+                    Some(palette.get_cell_color(age, 255).fade(opacity))
+                } else if states > 2 && state > 1 {
+                    let fade = (state - 1) as f32 / (states - 1) as f32;
+                    Some(palette.get_cell_color(1, 255).fade(opacity * (1.0 - fade)))
+                } else {
+                    None
+                };
+                let mut color = background;
+                if let Some(cell_color) = cell_color {
+                    blend_pixel(&mut color, cell_color);
+                }
+                
+                let cached = &mut self.colors[y * sim.width() + x];
+                if *cached != color {
+                    *cached = color;
+                    layout.fill_cell(frame, 1, x, y, Color::new(color[0], color[1], color[2], 255), 0);
+                    self.pixels_written += cell_pixels;
+                }
+            }
+        }
+    }
+}
+
+/// Circular spectrum around the center, colored by the palette as if each
+/// bar's energy were a cell's age
+pub fn draw_spectrum(frame: &mut [u8], width: usize, audio_frame: &AudioFrame, palette: &ColorPalette) {
+    let energies = radial::bar_energies(audio_frame, RADIAL_BARS);
+    radial::draw_radial(frame, width, &energies, |energy| {
+        palette.get_cell_color((energy * 255.0).round().max(1.0) as u8, 255)
+    });
+}
+
+/// Overall brightness from the analyzer's smoothed energy, never below `dim_floor`
+pub fn brightness(visualization: &VisualizationConfig, audio_frame: Option<&AudioFrame>) -> f32 {
+    let floor = visualization.dim_floor.clamp(0.0, 1.0);
+    let energy = audio_frame.map_or(0.0, |frame| frame.energy.smoothed.clamp(0.0, 1.0));
+    floor + (1.0 - floor) * energy.powf(visualization.dim_curve.max(0.01))
+}
+
+/// Scale every pixel's color by `brightness`
+pub fn dim(frame: &mut [u8], brightness: f32) {
+    if brightness < 1.0 {
+        for pixel in frame.chunks_exact_mut(4) {
+            for channel in &mut pixel[..3] {
+                *channel = (*channel as f32 * brightness) as u8;
+            }
+        }
+    }
+}
+
+/// Rotate every row of the frame `offset` pixels to the right (left when
+/// negative), wrapping around the edges and blending neighboring pixels for
+/// the fractional part so slow scrolling stays smooth
+pub fn scroll(frame: &mut [u8], width: usize, offset: f32) {
+    if width == 0 {
+        return;
+    }
+    let whole = offset.floor();
+    let fraction = offset - whole;
+    let shift = (whole as isize).rem_euclid(width as isize) as usize;
+    if shift == 0 && fraction == 0.0 {
+        return;
+    }
+    
+    let mut row = vec![0; width * 4];
+    for line in frame.chunks_exact_mut(width * 4) {
+        row.copy_from_slice(line);
+        for x in 0..width {
+            let near = (x + width - shift) % width;
+            let far = (near + width - 1) % width;
+            for channel in 0..3 {
+                let (near, far) = (row[near * 4 + channel] as f32, row[far * 4 + channel] as f32);
+                line[x * 4 + channel] = (near + (far - near) * fraction).round() as u8;
+            }
+        }
+    }
+}
+
+/// Magnify the frame by `factor` (at least 1) about its center, sampling
+/// between pixels so small changes in zoom stay smooth
+pub fn zoom(frame: &mut [u8], width: usize, factor: f32) {
+    if width == 0 || factor <= 1.0 {
+        return;
+    }
+    let height = frame.len() / 4 / width;
+    let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+    let source = frame.to_vec();
+    let sample = |x: usize, y: usize, channel: usize| source[(y * width + x) * 4 + channel] as f32;
+    
+    for y in 0..height {
+        let sy = (center_y + (y as f32 + 0.5 - center_y) / factor - 0.5).clamp(0.0, (height - 1) as f32);
+        let (y0, fy) = (sy.floor() as usize, sy.fract());
+        let y1 = (y0 + 1).min(height - 1);
+        for x in 0..width {
+            let sx = (center_x + (x as f32 + 0.5 - center_x) / factor - 0.5).clamp(0.0, (width - 1) as f32);
+            let (x0, fx) = (sx.floor() as usize, sx.fract());
+            let x1 = (x0 + 1).min(width - 1);
+            for channel in 0..3 {
+                let top = sample(x0, y0, channel) + (sample(x1, y0, channel) - sample(x0, y0, channel)) * fx;
+                let bottom = sample(x0, y1, channel) + (sample(x1, y1, channel) - sample(x0, y1, channel)) * fx;
+                frame[(y * width + x) * 4 + channel] = (top + (bottom - top) * fy).round() as u8;
+            }
+        }
+    }
+}
+
+/// Strength of a beat flash at `age` seconds after the beat: full for `hold`
+/// seconds so even a single-frame beat is seen, then fading out
+pub fn beat_flash_level(age: f32, hold: f32) -> f32 {
+    if age <= hold {
+        1.0
+    } else {
+        (1.0 - (age - hold) / BEAT_FLASH_FADE_SECS).max(0.0)
+    }
+}
+
+/// Lighten every pixel toward white by a beat flash at `level` (0-1)
+pub fn flash(frame: &mut [u8], level: f32) {
+    let amount = level.clamp(0.0, 1.0) * BEAT_FLASH_STRENGTH;
+    if amount > 0.0 {
+        for pixel in frame.chunks_exact_mut(4) {
+            for channel in &mut pixel[..3] {
+                *channel = (*channel as f32 + (255.0 - *channel as f32) * amount) as u8;
+            }
+        }
+    }
+}
+
+/// Render one frame of the simulation without a window, as the display draws
+/// it between generations: background, cells, spectrum and dimming, but no
+/// trails, cursor or overlays, and at the window's own resolution
+pub fn render_frame(
+    config: &Config,
+    sim: &GameOfLife,
+    palette: &ColorPalette,
+    audio_frame: Option<&AudioFrame>,
+    image: Option<&BackgroundImage>,
+) -> (Vec<u8>, Layout) {
+    let layout = Layout::new(config, (sim.width(), sim.height()));
+    let visualization = &config.visualization;
+    let mut frame = vec![0; layout.frame_size.0 * layout.frame_size.1 * 4];
+    
+    fill_background(&mut frame, 1, &layout, &[palette], image);
+    if visualization.mode.shows_grid() {
+        draw_cells(&mut frame, 1, &layout, sim, &[palette], visualization, 1.0);
+    }
+    if let (true, Some(audio_frame)) = (visualization.mode.shows_radial(), audio_frame) {
+        draw_spectrum(&mut frame, layout.frame_size.0, audio_frame, palette);
+    }
+    dim(&mut frame, brightness(visualization, audio_frame));
+    
+    (frame, layout)
+}
+
+/// Light gathered over many generations, like a long-exposure photo: every
+/// generation a cell is alive adds to its brightness, and older light fades
+/// by `decay` per generation so moving patterns leave streaks
+pub struct LongExposure {
+    width: usize,
+    height: usize,
+    light: Vec<f32>, // Accumulated brightness per cell
+    decay: f32,
+}
+
+impl LongExposure {
+    pub fn new(width: usize, height: usize, decay: f32) -> Self {
+        LongExposure {
+            width,
+            height,
+            light: vec![0.0; width * height],
+            decay: decay.clamp(0.0, 1.0),
+        }
+    }
+    
+    /// Add one generation's live cells; snapshots of another size are ignored
+    pub fn expose(&mut self, snapshot: &GridSnapshot) {
+        if snapshot.width != self.width || snapshot.height != self.height {
+            return;
+        }
+        for (light, &alive) in self.light.iter_mut().zip(&snapshot.cells) {
+            *light = *light * self.decay + alive as u8 as f32;
+        }
+    }
+    
+    /// Brightness of every cell, row by row, normalized so the brightest is 1.0
+    pub fn levels(&self) -> Vec<f32> {
+        let max = self.light.iter().copied().fold(0.0, f32::max);
+        if max <= 0.0 {
+            return vec![0.0; self.light.len()];
+        }
+        self.light.iter().map(|light| light / max).collect()
+    }
+    
+    /// Draw the exposure as the window would show the grid, each cell in the
+    /// palette's color for its brightness, blended over the background by it
+    pub fn render(&self, config: &Config, palette: &ColorPalette) -> (Vec<u8>, Layout) {
+        let layout = Layout::new(config, (self.width, self.height));
+        let mut frame = vec![0; layout.frame_size.0 * layout.frame_size.1 * 4];
+        fill_background(&mut frame, 1, &layout, &[palette], None);
+        
+        for (i, level) in self.levels().into_iter().enumerate() {
+            if level > 0.0 {
+                let color = palette.get_cell_color((level * 255.0).round().max(1.0) as u8, 255);
+                layout.fill_cell(&mut frame, 1, i % self.width, i / self.width, color.fade(level), 0);
+            }
+        }
+        (frame, layout)
+    }
+}
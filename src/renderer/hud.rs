@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+
+use crate::renderer::color::Color;
+
+/// Glyph cell size in font pixels, before scaling
+pub const GLYPH_WIDTH: usize = 3;
+pub const GLYPH_HEIGHT: usize = 5;
+
+/// Rows of a 3x5 glyph, top to bottom, with the high bit on the left
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '(' => [0b010, 0b100, 0b100, 0b100, 0b010],
+        ')' => [0b010, 0b001, 0b001, 0b001, 0b010],
+        ' ' => [0b000; GLYPH_HEIGHT],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010], // '?'
+    }
+}
+
+/// Width in pixels of `text` drawn at `scale`, including the gaps between glyphs
+pub fn text_width(text: &str, scale: usize) -> usize {
+    let chars = text.chars().count();
+    (chars * (GLYPH_WIDTH + 1)).saturating_sub(1) * scale
+}
+
+/// Height in pixels of one line of text drawn at `scale`
+pub fn line_height(scale: usize) -> usize {
+    (GLYPH_HEIGHT + 1) * scale
+}
+
+/// Draw `text` with its top-left corner at (x, y), clipped to the frame
+pub fn draw_text(frame: &mut [u8], frame_width: usize, x: usize, y: usize, text: &str, color: Color, scale: usize) {
+    for (i, c) in text.chars().enumerate() {
+        let left = x + i * (GLYPH_WIDTH + 1) * scale;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                    fill_rect(frame, frame_width, left + col * scale, y + row * scale, scale, scale, color);
+                }
+            }
+        }
+    }
+}
+
+/// Fill a rectangle of pixels, clipped to the frame
+pub fn fill_rect(frame: &mut [u8], frame_width: usize, x: usize, y: usize, width: usize, height: usize, color: Color) {
+    let frame_height = frame.len() / 4 / frame_width.max(1);
+    let rgba = color.to_rgba();
+    for py in y..(y + height).min(frame_height) {
+        for px in x..(x + width).min(frame_width) {
+            let idx = (py * frame_width + px) * 4;
+            frame[idx..idx + 4].copy_from_slice(&rgba);
+        }
+    }
+}
+
+/// The most recent values of a statistic, oldest first, for plotting
+#[derive(Debug, Clone)]
+pub struct History {
+    values: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        History { values: VecDeque::with_capacity(capacity), capacity: capacity.max(1) }
+    }
+    
+    /// Add the newest value, forgetting the oldest once full
+    pub fn push(&mut self, value: f32) {
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+    
+    pub fn latest(&self) -> Option<f32> {
+        self.values.back().copied()
+    }
+    
+    /// Lowest and highest value held, if any
+    pub fn range(&self) -> Option<(f32, f32)> {
+        let first = *self.values.front()?;
+        Some(self.values.iter().fold((first, first), |(low, high), &value| (low.min(value), high.max(value))))
+    }
+}
+
+/// Draw `history` as a line in a `width` x `height` box with its top-left
+/// corner at (x, y), clipped to the frame
+///
+/// The newest value sits on the right edge and a full history spans the box.
+/// The vertical axis fits the values held, lowest at the bottom and highest
+/// at the top; a flat line runs through the middle.
+pub fn draw_plot(frame: &mut [u8], frame_width: usize, (x, y): (usize, usize), (width, height): (usize, usize), history: &History, color: Color) {
+    let Some((low, high)) = history.range() else { return };
+    if width == 0 || height == 0 {
+        return;
+    }
+    let row = |value: f32| {
+        let level = if high > low { (value - low) / (high - low) } else { 0.5 };
+        y + ((1.0 - level) * (height - 1) as f32).round() as usize
+    };
+    let start = history.capacity - history.values.len();
+    let mut previous: Option<usize> = None;
+    for (i, &value) in history.values.iter().enumerate() {
+        let column = x + (start + i) * (width - 1) / (history.capacity - 1).max(1);
+        let current = row(value);
+        // Join each point to the last one with a vertical run so steep changes stay connected
+        let (top, bottom) = match previous {
+            Some(last) => (last.min(current), last.max(current)),
+            None => (current, current),
+        };
+        fill_rect(frame, frame_width, column, top, 1, bottom - top + 1, color);
+        previous = Some(current);
+    }
+}
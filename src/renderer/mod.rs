@@ -1,2 +1,6 @@
 pub mod display;
-pub mod color;
\ No newline at end of file
+pub mod color;
+pub mod draw;
+pub mod hud;
+pub mod radial;
+pub mod scene;
\ No newline at end of file
@@ -0,0 +1,95 @@
+use std::f32::consts::TAU;
+
+use crate::audio::analyzer::AudioFrame;
+use crate::renderer::color::Color;
+
+/// Number of bars around the circle
+pub const RADIAL_BARS: usize = 64;
+
+/// Radius of the empty center and the longest bar, as fractions of the
+/// smaller frame dimension
+const INNER_RADIUS: f32 = 0.15;
+const MAX_BAR_LENGTH: f32 = 0.3;
+
+/// Fraction of each bar's angular slot it fills, leaving gaps between bars
+const BAR_FILL: f32 = 0.6;
+
+/// Energy of each bar, clockwise from the top
+///
+/// Bass sits at the top and treble at the bottom, blending through the mids
+/// down both sides. Stereo frames draw the left channel on the left half and
+/// the right channel on the right.
+pub fn bar_energies(frame: &AudioFrame, bars: usize) -> Vec<f32> {
+    let bands = |left_side: bool| {
+        let side = if left_side { frame.left } else { frame.right };
+        match side {
+            Some(side) => [side.bass_energy, side.mid_energy, side.treble_energy],
+            None => [frame.bass_energy, frame.mid_energy, frame.treble_energy],
+        }
+    };
+    
+    (0..bars)
+        .map(|i| {
+            let turn = (i as f32 + 0.5) / bars as f32;
+            let [bass, mid, treble] = bands(turn > 0.5);
+            // 0 at the top, 1 at the bottom, on either side
+            let depth = 1.0 - (2.0 * turn - 1.0).abs();
+            let energy = if depth < 0.5 {
+                bass + (mid - bass) * depth * 2.0
+            } else {
+                mid + (treble - mid) * (depth * 2.0 - 1.0)
+            };
+            energy.clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+/// Draw `energies` as bars radiating from the center of the frame, blending
+/// partially covered pixels at the bar edges so they stay smooth at any angle
+pub fn draw_radial(frame: &mut [u8], width: usize, energies: &[f32], color: impl Fn(f32) -> Color) {
+    if energies.is_empty() || width == 0 {
+        return;
+    }
+    let height = frame.len() / 4 / width;
+    let size = width.min(height) as f32;
+    let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+    let inner = size * INNER_RADIUS;
+    let outer_max = inner + size * MAX_BAR_LENGTH;
+    let slot = TAU / energies.len() as f32;
+    let colors: Vec<[u8; 4]> = energies.iter().map(|&energy| color(energy).to_rgba()).collect();
+    
+    // Only the square around the longest possible bars can be touched
+    let reach = outer_max.ceil() as usize + 1;
+    let x_range = (center_x as usize).saturating_sub(reach)..(center_x as usize + reach).min(width);
+    let y_range = (center_y as usize).saturating_sub(reach)..(center_y as usize + reach).min(height);
+    for py in y_range {
+        for px in x_range.clone() {
+            let dx = px as f32 + 0.5 - center_x;
+            let dy = py as f32 + 0.5 - center_y;
+            let radius = (dx * dx + dy * dy).sqrt();
+            if radius < inner - 1.0 || radius > outer_max + 1.0 {
+                continue;
+            }
+            
+            // Clockwise angle from the top, and the bar whose slot it falls in
+            let angle = dx.atan2(-dy).rem_euclid(TAU);
+            let index = ((angle / slot) as usize).min(energies.len() - 1);
+            let outer = inner + size * MAX_BAR_LENGTH * energies[index];
+            
+            // Distance in pixels from the bar's edges gives its coverage of this pixel
+            let across = (angle - (index as f32 + 0.5) * slot).abs() * radius;
+            let half_width = slot * BAR_FILL / 2.0 * radius;
+            let coverage = (half_width - across + 0.5).clamp(0.0, 1.0)
+                * (radius - inner + 0.5).clamp(0.0, 1.0)
+                * (outer - radius + 0.5).clamp(0.0, 1.0);
+            if coverage <= 0.0 {
+                continue;
+            }
+            
+            let idx = (py * width + px) * 4;
+            for (channel, &target) in frame[idx..idx + 3].iter_mut().zip(&colors[index]) {
+                *channel = (*channel as f32 + (target as f32 - *channel as f32) * coverage).round() as u8;
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::ColorScheme;
+use crate::simulation::gol::GridSnapshot;
+
+/// Number of scene slots, bound to the digit keys 1-9
+pub const SCENE_SLOTS: usize = 9;
+
+/// Everything needed to jump back to a saved moment of a live set: the grid,
+/// its rule and pacing, and how the visuals react to the audio
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneState {
+    pub color_scheme: ColorScheme,
+    pub rule: String,      // Preset name or B/S notation
+    pub update_rate: f32,  // Generations per second
+    pub reactivity: f32,   // Response curve applied to audio frames
+    pub trails: bool,
+    pub grid: GridSnapshot,
+}
+
+impl SceneState {
+    /// Read a scene saved with `save`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(path.as_ref())
+            .with_context(|| format!("Failed to read scene {}", path.as_ref().display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid scene {}", path.as_ref().display()))
+    }
+    
+    /// Write the scene as TOML so it survives restarts
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let text = toml::to_string(self).context("Failed to serialize scene")?;
+        fs::write(path.as_ref(), text)
+            .with_context(|| format!("Failed to write scene {}", path.as_ref().display()))
+    }
+}
+
+/// File a numbered slot (1-based) is stored in, relative to the working directory
+pub fn scene_path(slot: usize) -> PathBuf {
+    PathBuf::from(format!("scene_{}.toml", slot))
+}
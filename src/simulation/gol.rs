@@ -1,9 +1,82 @@
-use rand::Rng;
+use anyhow::{bail, Result};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use std::time::Instant;
 
 use crate::audio::analyzer::AudioFrame;
 use crate::simulation::rules::{RuleSet, StandardRuleSet, AudioDrivenRuleSet};
-use crate::config::EdgeBehavior;
+use crate::config::{EdgeBehavior, InitialState, MutationMode, ResponseCurves, SimulationConfig};
+use crate::simulation::patterns::{self, pattern_size, Pattern};
+use crate::simulation::symmetry::Symmetry;
+
+/// Maximum length of a line in RLE output, per the format's convention
+const RLE_LINE_LENGTH: usize = 70;
+
+/// The eight cells around a cell, each counting once: (dx, dy, weight)
+const MOORE_KERNEL: [(isize, isize, i32); 8] = [
+    (-1, -1, 1), (0, -1, 1), (1, -1, 1),
+    (-1, 0, 1),              (1, 0, 1),
+    (-1, 1, 1),  (0, 1, 1),  (1, 1, 1),
+];
+
+/// Rectangular region of the grid, in cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    /// Whether the cell at (x, y) lies inside the rectangle
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Copy of the grid's cells, ages and generation count, see `GameOfLife::snapshot`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridSnapshot {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<bool>,
+    pub ages: Vec<u16>,
+    pub dying: Vec<u8>,
+    pub generation: u64,
+}
+
+/// Iterator over successive generations, see `GameOfLife::generations`
+pub struct Generations<'a, I> {
+    game: &'a mut GameOfLife,
+    frames: I,
+    started: bool, // Whether the starting grid has been yielded
+}
+
+impl<I: Iterator<Item = AudioFrame>> Iterator for Generations<'_, I> {
+    type Item = GridSnapshot;
+    
+    fn next(&mut self) -> Option<GridSnapshot> {
+        if self.started {
+            let frame = self.frames.next()?;
+            self.game.update(Some(&frame));
+        }
+        self.started = true;
+        Some(self.game.snapshot())
+    }
+}
+
+/// A rectangle of the grid that runs its own rule
+struct RuleZone {
+    region: Rect,
+    base: Arc<dyn RuleSet>,   // Rule set given for the zone
+    active: Arc<dyn RuleSet>, // The base, possibly modulated by audio
+}
+
+/// Callback run after every generation, see `GameOfLife::set_on_update`
+pub type UpdateObserver = Box<dyn FnMut(&GameOfLife) + Send>;
 
 /// The core Game of Life simulation
 pub struct GameOfLife {
@@ -11,16 +84,42 @@ pub struct GameOfLife {
     height: usize,
     grid: Vec<bool>,      // Current state
     next_grid: Vec<bool>, // Next state
-    age_grid: Vec<u8>,    // How many generations a cell has been alive
+    age_grid: Vec<u16>,   // How many generations a cell has been alive
+    dying_grid: Vec<u8>,  // Dying stage of dead cells under Generations rules (0 = not dying)
     last_update: Instant,
-    ruleset: Box<dyn RuleSet>,
+    base_ruleset: Arc<dyn RuleSet>, // Rule set chosen by config
+    ruleset: Arc<dyn RuleSet>,      // Active rule set (base, possibly modulated by audio)
+    rule_zones: Vec<RuleZone>,      // Areas running a rule of their own, the first containing a cell wins
     edge_behavior: EdgeBehavior,
+    wrap_override: (Option<bool>, Option<bool>), // Per-axis wrapping that replaces edge_behavior's
+    seed_region: Option<Rect>,      // Area random seeding is confined to (whole grid if unset)
+    max_lifespan: Option<u16>,      // Generations after which a cell dies of old age
+    sticky_age: Option<u16>,        // Age past which a cell gets one reprieve from the rule
+    energy_aging: f32,              // Extra age per step at full overall energy (0 = one per step)
+    kernel: Option<Vec<(isize, isize, i32)>>, // Weighted neighbor offsets replacing the Moore neighborhood
+    generations: bool,              // Honor multi-state (Generations) rules
+    audio_affects_rules: bool,      // Modulate the base rule set with incoming audio
+    stereo_gravity: f32,            // Strength of the lean toward the louder channel (0 = off)
+    lean: f32,                      // Current horizontal neighbor bias, -1 (left) to 1 (right)
+    mutation_mode: MutationMode,    // Cells random mutations may flip
+    glider_cooldown: Option<u32>,   // Generations between treble-launched gliders, if they're on
+    last_glider: Option<u64>,       // Generation the latest treble glider was launched in
+    response_curves: ResponseCurves, // How band energies bend the rule when audio affects it
+    seed: u64,                      // Master seed for initial state and mutations
+    generation: u64,                // Number of updates applied so far
+    changed: bool,                  // Whether the last update altered any cell's state
+    on_update: Option<UpdateObserver>, // Called with the new state after each update
 }
 
 impl GameOfLife {
     pub fn new(width: usize, height: usize, initial_density: f32) -> Self {
+        Self::with_seed(width, height, initial_density, rand::random())
+    }
+    
+    /// Create a simulation whose initial state and mutations are reproducible from `seed`
+    pub fn with_seed(width: usize, height: usize, initial_density: f32, seed: u64) -> Self {
         let cell_count = width * height;
-        let mut rng = rand::thread_rng();
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
         
         // Initialize grid with random cells
         let mut grid = vec![false; cell_count];
@@ -30,6 +129,8 @@ impl GameOfLife {
         
         let next_grid = vec![false; cell_count];
         let age_grid = vec![0; cell_count];
+        let dying_grid = vec![0; cell_count];
+        let ruleset: Arc<dyn RuleSet> = Arc::new(StandardRuleSet::new());
         
         GameOfLife {
             width,
@@ -37,41 +138,198 @@ impl GameOfLife {
             grid,
             next_grid,
             age_grid,
+            dying_grid,
             last_update: Instant::now(),
-            ruleset: Box::new(StandardRuleSet::new()),
+            base_ruleset: ruleset.clone(),
+            ruleset,
+            rule_zones: Vec::new(),
             edge_behavior: EdgeBehavior::Wrap,
+            wrap_override: (None, None),
+            seed_region: None,
+            max_lifespan: None,
+            sticky_age: None,
+            energy_aging: 0.0,
+            kernel: None,
+            generations: false,
+            audio_affects_rules: true,
+            stereo_gravity: 0.0,
+            lean: 0.0,
+            mutation_mode: MutationMode::Uniform,
+            glider_cooldown: None,
+            last_glider: None,
+            response_curves: ResponseCurves::default(),
+            seed,
+            generation: 0,
+            changed: false,
+            on_update: None,
         }
     }
     
+    /// Create a simulation from config: initial state, edge behavior and rule density
+    pub fn from_config(config: &SimulationConfig, seed: u64) -> Self {
+        let density = match config.initial_state {
+            InitialState::Random => config.initial_seed,
+            _ => 0.0,
+        };
+        let mut game = Self::with_seed(config.width, config.height, density, seed);
+        if let Some((x, y, width, height)) = config.seed_region {
+            let region = Rect { x, y, width, height };
+            game.set_seed_region(Some(region));
+            for (i, cell) in game.grid.iter_mut().enumerate() {
+                *cell = *cell && region.contains(i % config.width, i / config.width);
+            }
+        }
+        game.set_edge_behavior(config.edge_behavior.clone());
+        game.set_wrap(config.wrap_x, config.wrap_y);
+        game.set_max_lifespan(config.max_lifespan);
+        game.set_sticky_age(config.sticky_age);
+        game.set_energy_aging(config.energy_aging);
+        game.set_kernel(config.kernel.clone());
+        game.set_generations(config.generations);
+        game.set_audio_affects_rules(config.audio_affects_rules);
+        game.set_stereo_gravity(config.stereo_gravity);
+        game.set_mutation_mode(config.mutation_mode);
+        game.set_treble_gliders(config.treble_gliders.then_some(config.glider_cooldown));
+        game.set_response_curves(config.response_curves.clone());
+        
+        if let Some(cells) = patterns::initial_pattern(config.initial_state) {
+            let (pattern_width, pattern_height) = pattern_size(cells);
+            let x = config.width.saturating_sub(pattern_width) / 2;
+            let y = config.height.saturating_sub(pattern_height) / 2;
+            game.stamp_pattern(cells, x, y);
+        }
+        
+        // An embedded pattern replaces whatever the initial state put down
+        // (`Config::validate` has already rejected malformed ones)
+        if let Some(Ok(pattern)) = config.initial_pattern.as_deref().map(patterns::parse_cells) {
+            let (pattern_width, pattern_height) = pattern.size();
+            game.clear();
+            game.stamp_pattern(
+                &pattern.cells,
+                config.width.saturating_sub(pattern_width) / 2,
+                config.height.saturating_sub(pattern_height) / 2,
+            );
+        }
+        
+        game
+    }
+    
     /// Update the simulation with potential audio influence
     pub fn update(&mut self, audio_frame: Option<&AudioFrame>) {
         // If we have audio data, use it to affect the rules
+        if !self.audio_affects_rules {
+            self.ruleset = self.base_ruleset.clone();
+            for zone in self.rule_zones.iter_mut() {
+                zone.active = zone.base.clone();
+            }
+        } else if let Some(frame) = audio_frame {
+            let modulate = |base: &Arc<dyn RuleSet>| -> Arc<dyn RuleSet> {
+                Arc::new(AudioDrivenRuleSet::new(
+                    base.clone(),
+                    frame.bass_energy,
+                    frame.mid_energy,
+                    frame.treble_energy,
+                    &self.response_curves,
+                ))
+            };
+            self.ruleset = modulate(&self.base_ruleset);
+            let zone_rules: Vec<_> = self.rule_zones.iter().map(|zone| modulate(&zone.base)).collect();
+            for (zone, ruleset) in self.rule_zones.iter_mut().zip(zone_rules) {
+                zone.active = ruleset;
+            }
+        }
+        
+        // Lean growth toward the louder channel when stereo analysis reports a balance
         if let Some(frame) = audio_frame {
-            let ruleset = AudioDrivenRuleSet::new(
-                frame.bass_energy,
-                frame.mid_energy,
-                frame.treble_energy,
-            );
-            self.ruleset = Box::new(ruleset);
+            self.lean = frame.balance().unwrap_or(0.0) * self.stereo_gravity;
         }
 
+        // Loud passages push surviving cells through the color gradient faster
+        let energy = audio_frame.map_or(0.0, |frame| frame.overall_energy.max(0.0));
+        let age_step = 1 + (self.energy_aging * energy).round().min(u16::MAX as f32) as u16;
+        
+        // Mutations, chance rules and gliders draw from a per-generation stream so runs with the same seed match
+        let mut rng = self.generation_rng();
+        
+        // Treble onsets (e.g. hi-hats) send gliders in from the edges, at most one per cooldown
+        if let (Some(cooldown), Some(frame)) = (self.glider_cooldown, audio_frame) {
+            let rested = self.last_glider.is_none_or(|last| self.generation >= last + cooldown as u64);
+            if frame.treble_onset && rested {
+                self.launch_glider(&mut rng);
+                self.last_glider = Some(self.generation);
+            }
+        }
+        
+        // The main rule comes first, then each zone's
+        let rules: Vec<(Arc<dyn RuleSet>, f32, bool)> = std::iter::once(&self.ruleset)
+            .chain(self.rule_zones.iter().map(|zone| &zone.active))
+            .map(|ruleset| (ruleset.clone(), ruleset.mutation_chance(), ruleset.is_stochastic()))
+            .collect();
+        
+        let states = self.state_count();
+        let mut changed = false;
+        
         // Apply rules to calculate the next generation
         for y in 0..self.height {
             for x in 0..self.width {
                 let idx = y * self.width + x;
+                
+                // Dying cells advance toward death and can't be born meanwhile
+                let dying = self.dying_grid[idx];
+                if dying > 0 {
+                    self.dying_grid[idx] = if dying + 2 < states { dying + 1 } else { 0 };
+                    self.next_grid[idx] = false;
+                    changed = true;
+                    continue;
+                }
+                
+                // Neighbors are counted across zone borders as anywhere else
                 let neighbors = self.count_neighbors(x, y);
                 let current_state = self.grid[idx];
+                let zone = self.zone_at(x, y).map_or(0, |i| i + 1);
+                let (ruleset, mutation_chance, stochastic) = &rules[zone];
+                let (mutation_chance, stochastic) = (*mutation_chance, *stochastic);
                 
                 // Apply the ruleset to determine the next state
-                let next_state = self.ruleset.apply(current_state, neighbors);
+                let mut next_state = if stochastic {
+                    ruleset.apply_with_roll(current_state, neighbors, rng.gen())
+                } else {
+                    ruleset.apply(current_state, neighbors)
+                };
+                
+                // Established cells hang on once when the rule would kill them
+                let reprieved = current_state && !next_state && self.is_sticky(idx);
+                if reprieved {
+                    next_state = true;
+                }
+                
+                // Randomly flip the cell when the ruleset asks for mutations and
+                // the mutation mode lets them land here
+                if mutation_chance > 0.0 && self.can_mutate(x, y, neighbors) && rng.gen::<f32>() < mutation_chance {
+                    next_state = !next_state;
+                }
+                
+                // Cells that have reached the lifespan die of old age
+                if current_state && self.max_lifespan.is_some_and(|limit| self.age_grid[idx] >= limit) {
+                    next_state = false;
+                }
                 
                 self.next_grid[idx] = next_state;
+                changed |= next_state != current_state;
+                
+                // Under Generations rules a cell that stops surviving starts dying
+                if current_state && !next_state && states > 2 {
+                    self.dying_grid[idx] = 1;
+                }
                 
                 // Update cell age
                 if next_state {
-                    if current_state {
+                    if reprieved {
+                        // Back to the threshold, so the reprieve is spent until it ages past it again
+                        self.age_grid[idx] = self.sticky_age.unwrap_or(0);
+                    } else if current_state {
                         // Cell stays alive, increment age (with saturation)
-                        self.age_grid[idx] = self.age_grid[idx].saturating_add(1);
+                        self.age_grid[idx] = self.age_grid[idx].saturating_add(age_step);
                     } else {
                         // Cell was born, reset age
                         self.age_grid[idx] = 1;
@@ -85,67 +343,150 @@ impl GameOfLife {
         
         // Swap grids for next iteration
         std::mem::swap(&mut self.grid, &mut self.next_grid);
+        self.changed = changed;
+        self.generation += 1;
         self.last_update = Instant::now();
+        
+        // Taken out while it runs so the observer can borrow the whole simulation
+        if let Some(mut observer) = self.on_update.take() {
+            observer(self);
+            self.on_update = Some(observer);
+        }
+    }
+    
+    /// Step the simulation once per audio frame, with no real-time pacing
+    ///
+    /// Yields the current grid first and then the grid after each frame, so
+    /// `n` frames give generations 0 through `n`. Handy for dumping a run to
+    /// disk offline.
+    pub fn generations<I: IntoIterator<Item = AudioFrame>>(&mut self, frames: I) -> Generations<'_, I::IntoIter> {
+        Generations {
+            game: self,
+            frames: frames.into_iter(),
+            started: false,
+        }
+    }
+    
+    /// Run `observer` at the end of every `update`, e.g. to stream the grid elsewhere
+    ///
+    /// It sees the simulation after the generation has advanced. Replaces any
+    /// previous observer; `clear_on_update` removes it.
+    pub fn set_on_update(&mut self, observer: UpdateObserver) {
+        self.on_update = Some(observer);
+    }
+    
+    /// Remove the observer set with `set_on_update`
+    pub fn clear_on_update(&mut self) {
+        self.on_update = None;
+    }
+    
+    /// Build the mutation RNG for the current generation from the master seed
+    fn generation_rng(&self) -> ChaCha8Rng {
+        let mut seed = [0u8; 32];
+        seed[..8].copy_from_slice(&self.seed.to_le_bytes());
+        seed[8..16].copy_from_slice(&self.generation.to_le_bytes());
+        ChaCha8Rng::from_seed(seed)
+    }
+    
+    /// Stamp a glider at a random point along a random edge, turned to head
+    /// into the grid
+    fn launch_glider(&mut self, rng: &mut ChaCha8Rng) {
+        const SIZE: usize = 3;
+        if self.width < SIZE || self.height < SIZE {
+            return;
+        }
+        let (far_x, far_y) = (self.width - SIZE, self.height - SIZE);
+        let slant = rng.gen::<bool>();
+        
+        // GLIDER heads down and to the right; mirror it to point away from the edge
+        let (x, y, mirror_x, mirror_y) = match rng.gen_range(0..4) {
+            0 => (0, rng.gen_range(0..=far_y), false, slant),     // Left edge
+            1 => (far_x, rng.gen_range(0..=far_y), true, slant),  // Right edge
+            2 => (rng.gen_range(0..=far_x), 0, slant, false),     // Top edge
+            _ => (rng.gen_range(0..=far_x), far_y, slant, true),  // Bottom edge
+        };
+        let cells: Vec<_> = patterns::GLIDER.iter()
+            .map(|&(dx, dy)| (
+                if mirror_x { SIZE - 1 - dx } else { dx },
+                if mirror_y { SIZE - 1 - dy } else { dy },
+            ))
+            .collect();
+        self.stamp_pattern(&cells, x, y);
     }
     
     /// Count the number of live neighbors for a cell
+    ///
+    /// With a kernel, each live cell under it adds its weight instead of one.
+    /// With a stereo lean, neighbors on the quieter side weigh more and those
+    /// on the louder side less, so cells facing the louder channel are born
+    /// more readily and patterns creep toward it. The weighted count is rounded
+    /// and clamped to 0-255.
     fn count_neighbors(&self, x: usize, y: usize) -> u8 {
-        let mut count = 0;
-        
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                // Skip the center cell (self)
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                
-                let nx = self.wrap_x(x as isize + dx);
-                let ny = self.wrap_y(y as isize + dy);
-                
-                if let Some(idx) = self.get_index(nx, ny) {
-                    if self.grid[idx] {
-                        count += 1;
-                    }
-                } else {
-                    // Handle edge behavior for out-of-bounds cells
-                    match self.edge_behavior {
-                        EdgeBehavior::Wrap => {}, // Already handled by wrap_x/y
-                        EdgeBehavior::Dead => {}, // Count as dead, do nothing
-                        EdgeBehavior::Alive => count += 1, // Count as alive
-                    }
-                }
+        let alive_at = |dx: isize, dy: isize| {
+            let (nx, ny) = self.wrap(x as isize + dx, y as isize + dy);
+            
+            // Cells past an edge that doesn't wrap are dead, or alive with EdgeBehavior::Alive
+            match self.get_index(nx, ny) {
+                Some(idx) => self.grid[idx],
+                None => matches!(self.edge_behavior, EdgeBehavior::Alive),
             }
+        };
+        
+        // Plain counting, kept off the float path unless a lean or kernel weighs the neighbors
+        if self.kernel.is_none() && self.lean == 0.0 {
+            return MOORE_KERNEL.iter().filter(|&&(dx, dy, _)| alive_at(dx, dy)).count() as u8;
         }
         
-        count
+        let lean = |dx: isize| 1.0 - self.lean * dx.signum() as f32;
+        let weights: &[(isize, isize, i32)] = self.kernel.as_deref().unwrap_or(&MOORE_KERNEL);
+        let count: f32 = weights.iter()
+            .filter(|&&(dx, dy, _)| alive_at(dx, dy))
+            .map(|&(dx, _, weight)| weight as f32 * lean(dx))
+            .sum();
+        count.round().clamp(0.0, u8::MAX as f32) as u8
     }
     
-    /// Wrap x-coordinate based on edge behavior
-    fn wrap_x(&self, x: isize) -> isize {
-        match self.edge_behavior {
-            EdgeBehavior::Wrap => {
-                if x < 0 {
-                    (self.width as isize + x) % self.width as isize
-                } else {
-                    x % self.width as isize
-                }
-            },
-            _ => x, // For Dead/Alive edge behaviors, don't wrap
+    /// Map a neighbor coordinate onto the grid according to the edge topology
+    ///
+    /// Coordinates that stay outside the grid are left for `count_neighbors`
+    /// to resolve as dead or alive.
+    fn wrap(&self, x: isize, y: isize) -> (isize, isize) {
+        let (wrap_x, wrap_y) = self.wrap_axes();
+        let x = if wrap_x { self.wrap_x(x) } else { x };
+        if wrap_y && (y < 0 || y >= self.height as isize) {
+            let y = self.wrap_y(y);
+            match self.edge_behavior {
+                // Crossing the top/bottom seam mirrors the x axis
+                EdgeBehavior::KleinBottle => (self.width as isize - 1 - x, y),
+                _ => (x, y),
+            }
+        } else {
+            (x, y)
         }
     }
     
-    /// Wrap y-coordinate based on edge behavior
+    /// Whether the left/right and top/bottom edges wrap: the per-axis
+    /// overrides if set, otherwise what the edge behavior implies
+    fn wrap_axes(&self) -> (bool, bool) {
+        let (default_x, default_y) = match self.edge_behavior {
+            EdgeBehavior::Wrap | EdgeBehavior::KleinBottle => (true, true),
+            EdgeBehavior::Cylinder => (true, false),
+            EdgeBehavior::Dead | EdgeBehavior::Alive => (false, false),
+        };
+        (self.wrap_override.0.unwrap_or(default_x), self.wrap_override.1.unwrap_or(default_y))
+    }
+    
+    /// Wrap x-coordinate around the grid width
+    ///
+    /// On grids one or two cells wide a cell's neighbors wrap onto itself or
+    /// each other, so they are counted more than once, as on a real torus.
+    fn wrap_x(&self, x: isize) -> isize {
+        x.rem_euclid((self.width as isize).max(1))
+    }
+    
+    /// Wrap y-coordinate around the grid height
     fn wrap_y(&self, y: isize) -> isize {
-        match self.edge_behavior {
-            EdgeBehavior::Wrap => {
-                if y < 0 {
-                    (self.height as isize + y) % self.height as isize
-                } else {
-                    y % self.height as isize
-                }
-            },
-            _ => y, // For Dead/Alive edge behaviors, don't wrap
-        }
+        y.rem_euclid((self.height as isize).max(1))
     }
     
     /// Convert x,y coordinates to grid index if valid
@@ -162,6 +503,146 @@ impl GameOfLife {
         if x < self.width && y < self.height {
             let idx = y * self.width + x;
             self.grid[idx] = alive;
+            self.age_grid[idx] = if alive { self.age_grid[idx].max(1) } else { 0 };
+            self.dying_grid[idx] = 0;
+        }
+    }
+    
+    /// Flip a single cell between alive and dead
+    pub fn toggle_cell(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            let alive = self.grid[y * self.width + x];
+            self.set_cell(x, y, !alive);
+        }
+    }
+    
+    /// Flip the cell at a position given as fractions of the grid's width and
+    /// height, e.g. from a touchscreen; positions outside 0.0-1.0 hit the edge
+    pub fn toggle_cell_normalized(&mut self, nx: f32, ny: f32) {
+        let cell = |n: f32, size: usize| ((n.clamp(0.0, 1.0) * size as f32) as usize).min(size.saturating_sub(1));
+        self.toggle_cell(cell(nx, self.width), cell(ny, self.height));
+    }
+    
+    /// Set every cell within `radius` of (cx, cy) to `alive`, clipped to the grid
+    pub fn set_cells_in_radius(&mut self, cx: usize, cy: usize, radius: usize, alive: bool) {
+        for (dx, dy) in disk_offsets(radius) {
+            let x = cx as isize + dx;
+            let y = cy as isize + dy;
+            if x >= 0 && y >= 0 {
+                self.set_cell(x as usize, y as usize, alive);
+            }
+        }
+    }
+    
+    /// Smallest region containing every live cell, or None if the grid is empty
+    pub fn live_bounds(&self) -> Option<Rect> {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.grid[y * self.width + x] {
+                    let (x0, y0, x1, y1) = bounds.unwrap_or((x, y, x, y));
+                    bounds = Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y)));
+                }
+            }
+        }
+        bounds.map(|(x0, y0, x1, y1)| Rect { x: x0, y: y0, width: x1 - x0 + 1, height: y1 - y0 + 1 })
+    }
+    
+    /// Copy the live cells of a region (or the whole grid) into a pattern
+    pub fn to_pattern(&self, region: Option<Rect>) -> Pattern {
+        let region = region.unwrap_or(Rect { x: 0, y: 0, width: self.width, height: self.height });
+        let x_end = (region.x + region.width).min(self.width);
+        let y_end = (region.y + region.height).min(self.height);
+        
+        let mut cells = Vec::new();
+        for y in region.y..y_end {
+            for x in region.x..x_end {
+                if self.grid[y * self.width + x] {
+                    cells.push((x - region.x, y - region.y));
+                }
+            }
+        }
+        Pattern { name: None, cells }
+    }
+    
+    /// Encode a region (or the whole grid) as an RLE pattern
+    ///
+    /// Trailing dead cells of each row and trailing empty rows are omitted, and
+    /// the header records the base rule when it has B/S notation.
+    pub fn to_rle(&self, region: Option<Rect>) -> String {
+        let region = region.unwrap_or(Rect { x: 0, y: 0, width: self.width, height: self.height });
+        let x_end = (region.x + region.width).min(self.width);
+        let y_end = (region.y + region.height).min(self.height);
+        
+        let mut tokens = Vec::new();
+        let mut pending_rows = 0;
+        for y in region.y..y_end {
+            // Runs of (alive, length) with trailing dead cells dropped
+            let mut runs: Vec<(bool, usize)> = Vec::new();
+            for x in region.x..x_end {
+                let alive = self.grid[y * self.width + x];
+                match runs.last_mut() {
+                    Some((state, length)) if *state == alive => *length += 1,
+                    _ => runs.push((alive, 1)),
+                }
+            }
+            if let Some((false, _)) = runs.last() {
+                runs.pop();
+            }
+            
+            if runs.is_empty() {
+                pending_rows += 1;
+                continue;
+            }
+            if pending_rows > 0 {
+                tokens.push(rle_run(pending_rows, '$'));
+            }
+            for (alive, length) in runs {
+                tokens.push(rle_run(length, if alive { 'o' } else { 'b' }));
+            }
+            pending_rows = 1;
+        }
+        tokens.push("!".to_string());
+        
+        let mut rle = format!("x = {}, y = {}", x_end.saturating_sub(region.x), y_end.saturating_sub(region.y));
+        if let Some(rule) = self.base_ruleset.notation() {
+            rle.push_str(&format!(", rule = {}", rule));
+        }
+        rle.push('\n');
+        
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length + token.len() > RLE_LINE_LENGTH {
+                rle.push('\n');
+                line_length = 0;
+            }
+            line_length += token.len();
+            rle.push_str(&token);
+        }
+        rle.push('\n');
+        rle
+    }
+    
+    /// Mirror every live cell onto its symmetric partners
+    pub fn symmetrize(&mut self, mode: Symmetry) {
+        let live: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.is_cell_alive(x, y))
+            .collect();
+        
+        for (x, y) in live {
+            for (px, py) in mode.partners(x, y, self.width, self.height) {
+                self.set_cell(px, py, true);
+            }
+        }
+    }
+    
+    /// Bring the cells of a pattern to life with its top-left corner at (x, y)
+    ///
+    /// Cells falling outside the grid are skipped.
+    pub fn stamp_pattern(&mut self, cells: &[(usize, usize)], x: usize, y: usize) {
+        for &(dx, dy) in cells {
+            self.set_cell(x + dx, y + dy, true);
         }
     }
     
@@ -175,26 +656,98 @@ impl GameOfLife {
         }
     }
     
+    /// Get the Generations state of a cell: 0 dead, 1 alive, 2 and up dying
+    pub fn cell_state(&self, x: usize, y: usize) -> u8 {
+        if x < self.width && y < self.height {
+            let idx = y * self.width + x;
+            if self.grid[idx] {
+                1
+            } else if self.dying_grid[idx] > 0 {
+                self.dying_grid[idx] + 1
+            } else {
+                0
+            }
+        } else {
+            0
+        }
+    }
+    
+    /// Number of cell states in play: above 2 only for enabled Generations rules
+    pub fn state_count(&self) -> u8 {
+        if self.generations {
+            self.base_ruleset.states()
+        } else {
+            2
+        }
+    }
+    
     /// Get the age of a specific cell
+    ///
+    /// Ages beyond 255 are reported as 255.
     pub fn cell_age(&self, x: usize, y: usize) -> u8 {
         if x < self.width && y < self.height {
             let idx = y * self.width + x;
-            self.age_grid[idx]
+            self.age_grid[idx].min(u8::MAX as u16) as u8
+        } else {
+            0
+        }
+    }
+    
+    /// Number of live neighbors of a cell under the current edge behavior
+    pub fn cell_neighbors(&self, x: usize, y: usize) -> u8 {
+        if x < self.width && y < self.height {
+            self.count_neighbors(x, y)
         } else {
             0
         }
     }
     
+    /// Whether the active rule set (including any audio modulation) would have
+    /// the cell alive next generation, ignoring mutations and lifespan
+    ///
+    /// Cells inside a rule zone answer with the zone's rule, as `update` does.
+    pub fn rule_decision(&self, x: usize, y: usize) -> bool {
+        let ruleset = self.zone_at(x, y).map_or(&self.ruleset, |i| &self.rule_zones[i].active);
+        ruleset.apply(self.is_cell_alive(x, y), self.cell_neighbors(x, y))
+    }
+    
+    /// Index of the rule zone whose rule a cell follows, the first listed
+    /// containing it, or None for the grid's own rule
+    fn zone_at(&self, x: usize, y: usize) -> Option<usize> {
+        self.rule_zones.iter().position(|zone| zone.region.contains(x, y))
+    }
+    
     /// Get simulation width
     pub fn width(&self) -> usize {
         self.width
     }
     
+    /// Get the master seed driving initial state and mutations
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    
+    /// Whether the last `update` born, killed or advanced the dying stage of
+    /// any cell; ages still count up on a grid that didn't change
+    pub fn changed_last_update(&self) -> bool {
+        self.changed
+    }
+    
+    /// Get the number of generations simulated so far
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+    
     /// Get simulation height
     pub fn height(&self) -> usize {
         self.height
     }
     
+    /// Count the live cells
+    pub fn population(&self) -> usize {
+        self.grid.iter().filter(|&&alive| alive).count()
+    }
+    
     /// Clear the grid (all cells dead)
     pub fn clear(&mut self) {
         for cell in self.grid.iter_mut() {
@@ -203,21 +756,221 @@ impl GameOfLife {
         for age in self.age_grid.iter_mut() {
             *age = 0;
         }
+        for dying in self.dying_grid.iter_mut() {
+            *dying = 0;
+        }
+    }
+    
+    /// Restart every live cell at age 1 (and dead ones at 0), keeping the cells
+    /// themselves, so age-based color gradients begin afresh
+    pub fn reset_ages(&mut self) {
+        for (age, &alive) in self.age_grid.iter_mut().zip(&self.grid) {
+            *age = alive as u16;
+        }
     }
     
-    /// Randomize the grid with a specified density
+    /// Randomize the grid with a specified density, within the seed region if one is set
     pub fn randomize(&mut self, density: f32) {
         let mut rng = rand::thread_rng();
-        for cell in self.grid.iter_mut() {
-            *cell = rng.gen::<f32>() < density;
+        let region = self.seed_region
+            .unwrap_or(Rect { x: 0, y: 0, width: self.width, height: self.height });
+        for (i, cell) in self.grid.iter_mut().enumerate() {
+            *cell = region.contains(i % self.width, i / self.width) && rng.gen::<f32>() < density;
         }
         for age in self.age_grid.iter_mut() {
             *age = 0;
         }
+        for dying in self.dying_grid.iter_mut() {
+            *dying = 0;
+        }
+    }
+    
+    /// Capture the current cell states so they can be restored later
+    pub fn snapshot(&self) -> GridSnapshot {
+        GridSnapshot {
+            width: self.width,
+            height: self.height,
+            cells: self.grid.clone(),
+            ages: self.age_grid.clone(),
+            dying: self.dying_grid.clone(),
+            generation: self.generation,
+        }
+    }
+    
+    /// Restore cell states from a snapshot of a grid with the same dimensions
+    pub fn restore(&mut self, snapshot: &GridSnapshot) -> Result<()> {
+        let cells = self.width * self.height;
+        if snapshot.width != self.width || snapshot.height != self.height {
+            bail!(
+                "Snapshot is {}x{} but the grid is {}x{}",
+                snapshot.width, snapshot.height, self.width, self.height
+            );
+        }
+        if snapshot.cells.len() != cells || snapshot.ages.len() != cells || snapshot.dying.len() != cells {
+            bail!("Snapshot doesn't hold {} cells", cells);
+        }
+        
+        self.grid.copy_from_slice(&snapshot.cells);
+        self.age_grid.copy_from_slice(&snapshot.ages);
+        self.dying_grid.copy_from_slice(&snapshot.dying);
+        self.generation = snapshot.generation;
+        Ok(())
+    }
+    
+    /// Set the age at which cells die regardless of neighbors (None disables)
+    pub fn set_max_lifespan(&mut self, lifespan: Option<u16>) {
+        self.max_lifespan = lifespan;
+    }
+    
+    /// Set the age past which cells survive one generation the rule would end
+    /// (None disables)
+    ///
+    /// A reprieved cell's age drops back to the threshold, so it only resists
+    /// again once it has outlived the threshold a second time. Long-lived
+    /// structures thin out over a few generations instead of vanishing at once.
+    pub fn set_sticky_age(&mut self, age: Option<u16>) {
+        self.sticky_age = age;
+    }
+    
+    /// Enable multi-state Generations rules; when disabled they run as plain B/S
+    pub fn set_generations(&mut self, enabled: bool) {
+        self.generations = enabled;
+        if !enabled {
+            for dying in self.dying_grid.iter_mut() {
+                *dying = 0;
+            }
+        }
+    }
+    
+    /// Let audio frames modulate the rules; when disabled the base rule set runs unchanged
+    pub fn set_audio_affects_rules(&mut self, enabled: bool) {
+        self.audio_affects_rules = enabled;
+        if !enabled {
+            self.ruleset = self.base_ruleset.clone();
+        }
+    }
+    
+    /// How strongly stereo balance tilts neighbor counting (0 disables, 1 is strongest)
+    ///
+    /// Only frames from stereo analysis carry a balance; mono frames leave the
+    /// counts unweighted.
+    pub fn set_stereo_gravity(&mut self, gravity: f32) {
+        self.stereo_gravity = gravity.clamp(0.0, 1.0);
+        if self.stereo_gravity == 0.0 {
+            self.lean = 0.0;
+        }
+    }
+    
+    /// How many extra generations of age surviving cells gain per step at full
+    /// overall energy, so they mature faster in loud passages (0 disables)
+    pub fn set_energy_aging(&mut self, extra: f32) {
+        self.energy_aging = extra.max(0.0);
+    }
+    
+    /// Weigh neighbors with a kernel of rows centered on the cell instead of
+    /// counting the eight around it (None restores the Moore neighborhood)
+    ///
+    /// The kernel should have an odd number of rows and columns. Zero weights
+    /// are skipped, and the center is counted like any other weight, so leave
+    /// it at 0 to exclude the cell itself.
+    pub fn set_kernel(&mut self, kernel: Option<Vec<Vec<i32>>>) {
+        // The Moore kernel itself keeps the faster unweighted count
+        let moore = vec![vec![1, 1, 1], vec![1, 0, 1], vec![1, 1, 1]];
+        self.kernel = kernel.filter(|rows| *rows != moore).map(|rows| {
+            let (cy, cx) = (rows.len() as isize / 2, rows.first().map_or(0, Vec::len) as isize / 2);
+            rows.iter().enumerate()
+                .flat_map(|(row, weights)| weights.iter().enumerate().map(move |(col, &weight)| {
+                    (col as isize - cx, row as isize - cy, weight)
+                }))
+                .filter(|&(_, _, weight)| weight != 0)
+                .collect()
+        });
+    }
+    
+    /// Launch a glider from the edge on treble onsets, at most once every
+    /// `cooldown` generations (None turns them off)
+    pub fn set_treble_gliders(&mut self, cooldown: Option<u32>) {
+        self.glider_cooldown = cooldown;
+    }
+    
+    /// Set how band energies bend the rule while audio affects it
+    pub fn set_response_curves(&mut self, curves: ResponseCurves) {
+        self.response_curves = curves;
+    }
+    
+    /// Choose which cells audio-driven mutations may flip
+    pub fn set_mutation_mode(&mut self, mode: MutationMode) {
+        self.mutation_mode = mode;
+    }
+    
+    /// Whether the cell at `idx` is old enough to resist the rule killing it
+    fn is_sticky(&self, idx: usize) -> bool {
+        self.sticky_age.is_some_and(|age| self.age_grid[idx] > age)
+    }
+    
+    /// Whether the mutation mode allows flipping the cell at (x, y), which has
+    /// `neighbors` live neighbors
+    fn can_mutate(&self, x: usize, y: usize, neighbors: u8) -> bool {
+        match self.mutation_mode {
+            MutationMode::Uniform => true,
+            MutationMode::EdgesOnly => x == 0 || y == 0 || x + 1 == self.width || y + 1 == self.height,
+            MutationMode::NearLiveCells => neighbors > 0,
+        }
     }
     
     /// Set edge behavior
     pub fn set_edge_behavior(&mut self, behavior: EdgeBehavior) {
         self.edge_behavior = behavior;
     }
+    
+    /// Confine random seeding to a rectangle, leaving the rest of the grid empty
+    pub fn set_seed_region(&mut self, region: Option<Rect>) {
+        self.seed_region = region;
+    }
+    
+    /// Override whether each axis wraps (None keeps the edge behavior's choice)
+    ///
+    /// Edges that don't wrap are filled as the edge behavior says: alive for
+    /// `Alive`, dead for everything else.
+    pub fn set_wrap(&mut self, wrap_x: Option<bool>, wrap_y: Option<bool>) {
+        self.wrap_override = (wrap_x, wrap_y);
+    }
+    
+    /// Set the base rule set; audio modulation is layered on top of it
+    pub fn set_ruleset(&mut self, ruleset: Box<dyn RuleSet>) {
+        self.base_ruleset = Arc::from(ruleset);
+        self.ruleset = self.base_ruleset.clone();
+    }
+    
+    /// Run other rules inside parts of the grid, e.g. HighLife on the right half
+    ///
+    /// Where zones overlap the first one listed applies; everywhere else keeps
+    /// the main rule set. Zones follow the audio like the main rule, and
+    /// neighbors are counted across their borders as usual. Dying states come
+    /// from the main rule, so zones should use two-state rules.
+    pub fn set_rule_zones(&mut self, zones: Vec<(Rect, Box<dyn RuleSet>)>) {
+        self.rule_zones = zones.into_iter()
+            .map(|(region, ruleset)| {
+                let base: Arc<dyn RuleSet> = Arc::from(ruleset);
+                RuleZone { region, active: base.clone(), base }
+            })
+            .collect();
+    }
+}
+
+/// One RLE run, with the count omitted for a single cell
+fn rle_run(length: usize, tag: char) -> String {
+    if length == 1 {
+        tag.to_string()
+    } else {
+        format!("{}{}", length, tag)
+    }
+}
+
+/// Offsets (dx, dy) of the cells covered by a disk-shaped brush
+pub fn disk_offsets(radius: usize) -> impl Iterator<Item = (isize, isize)> {
+    let r = radius as isize;
+    (-r..=r)
+        .flat_map(move |dy| (-r..=r).map(move |dx| (dx, dy)))
+        .filter(move |&(dx, dy)| dx * dx + dy * dy <= r * r)
 }
\ No newline at end of file
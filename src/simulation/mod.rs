@@ -1,2 +1,4 @@
 pub mod gol;
-pub mod rules;
\ No newline at end of file
+pub mod patterns;
+pub mod rules;
+pub mod symmetry;
\ No newline at end of file
@@ -0,0 +1,207 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::config::InitialState;
+
+/// Live cells of a pattern as (x, y) offsets from its top-left corner
+pub type PatternCells = &'static [(usize, usize)];
+
+/// Acorn: a methuselah that takes 5206 generations to stabilize
+pub const ACORN: PatternCells = &[
+    (1, 0),
+    (3, 1),
+    (0, 2), (1, 2), (4, 2), (5, 2), (6, 2),
+];
+
+/// R-pentomino: the smallest methuselah, stabilizing after 1103 generations
+pub const R_PENTOMINO: PatternCells = &[
+    (1, 0), (2, 0),
+    (0, 1), (1, 1),
+    (1, 2),
+];
+
+/// Gosper glider gun: emits a glider every 30 generations
+pub const GOSPER_GLIDER_GUN: PatternCells = &[
+    (24, 0),
+    (22, 1), (24, 1),
+    (12, 2), (13, 2), (20, 2), (21, 2), (34, 2), (35, 2),
+    (11, 3), (15, 3), (20, 3), (21, 3), (34, 3), (35, 3),
+    (0, 4), (1, 4), (10, 4), (16, 4), (20, 4), (21, 4),
+    (0, 5), (1, 5), (10, 5), (14, 5), (16, 5), (17, 5), (22, 5), (24, 5),
+    (10, 6), (16, 6), (24, 6),
+    (11, 7), (15, 7),
+    (12, 8), (13, 8),
+];
+
+/// Glider, heading down and to the right
+pub const GLIDER: PatternCells = &[
+    (1, 0),
+    (2, 1),
+    (0, 2), (1, 2), (2, 2),
+];
+
+/// Block: the simplest still life
+pub const BLOCK: PatternCells = &[
+    (0, 0), (1, 0),
+    (0, 1), (1, 1),
+];
+
+/// A pattern loaded from a file or exported from the grid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub name: Option<String>,       // Name from the file's comments, if given
+    pub cells: Vec<(usize, usize)>, // Live cells as offsets from the top-left corner
+}
+
+impl Pattern {
+    /// Bounding box (width, height) of the pattern
+    pub fn size(&self) -> (usize, usize) {
+        pattern_size(&self.cells)
+    }
+    
+    /// Serialize as plaintext `.cells` (`O` alive, `.` dead, trailing dead cells trimmed)
+    pub fn to_cells(&self) -> String {
+        let (width, height) = self.size();
+        let mut rows = vec![vec!['.'; width]; height];
+        for &(x, y) in &self.cells {
+            rows[y][x] = 'O';
+        }
+        
+        let mut text = String::new();
+        if let Some(name) = &self.name {
+            text.push_str(&format!("!Name: {}\n", name));
+        }
+        for row in rows {
+            let row: String = row.into_iter().collect();
+            let trimmed = row.trim_end_matches('.');
+            // Keep a single dot so empty rows stay visible
+            text.push_str(if trimmed.is_empty() { "." } else { trimmed });
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Load a pattern file, choosing the format from its extension (`.cells` or `.rle`)
+pub fn load_pattern(path: &Path) -> Result<Pattern> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read pattern {}", path.display()))?;
+    
+    match path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_ascii_lowercase()) {
+        Some(ext) if ext == "cells" => parse_cells(&text),
+        _ => parse_rle(&text),
+    }
+}
+
+/// Bounding box (width, height) of a pattern
+pub fn pattern_size(cells: &[(usize, usize)]) -> (usize, usize) {
+    let width = cells.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|&(_, y)| y + 1).max().unwrap_or(0);
+    (width, height)
+}
+
+/// Cells to place for a preset initial state, or None for Random/Empty
+pub fn initial_pattern(state: InitialState) -> Option<PatternCells> {
+    match state {
+        InitialState::Random | InitialState::Empty => None,
+        InitialState::Acorn => Some(ACORN),
+        InitialState::RPentomino => Some(R_PENTOMINO),
+        InitialState::GliderGun => Some(GOSPER_GLIDER_GUN),
+        InitialState::CenteredBlock => Some(BLOCK),
+    }
+}
+
+/// RLE patterns compiled into the binary for the in-app pattern library
+pub const LIBRARY: &[&str] = &[
+    include_str!("../../assets/patterns/glider.rle"),
+    include_str!("../../assets/patterns/lwss.rle"),
+    include_str!("../../assets/patterns/gosper_glider_gun.rle"),
+    include_str!("../../assets/patterns/pulsar.rle"),
+];
+
+/// Parse the bundled pattern library, in `LIBRARY` order
+pub fn library() -> Vec<Pattern> {
+    LIBRARY.iter()
+        .map(|rle| parse_rle(rle).expect("bundled patterns are valid RLE"))
+        .collect()
+}
+
+/// Parse a pattern in RLE format
+///
+/// `#N` supplies the name; other `#` comment lines and the `x = .., y = ..`
+/// header are skipped. The body is read up to the terminating `!`. Any state
+/// other than `b`/`.` counts as alive.
+pub fn parse_rle(text: &str) -> Result<Pattern> {
+    let mut name = None;
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    let mut count: Option<usize> = None;
+    
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(comment) = line.strip_prefix("#N") {
+            name = Some(comment.trim().to_string());
+            continue;
+        }
+        if line.starts_with('#') || line.starts_with('x') {
+            continue;
+        }
+        
+        for c in line.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                count = Some(count.unwrap_or(0) * 10 + digit as usize);
+                continue;
+            }
+            
+            let run = count.take().unwrap_or(1);
+            match c {
+                'b' | '.' => x += run,
+                '$' => {
+                    y += run;
+                    x = 0;
+                },
+                '!' => return Ok(Pattern { name, cells }),
+                c if c.is_ascii_alphabetic() => {
+                    cells.extend((x..x + run).map(|cx| (cx, y)));
+                    x += run;
+                },
+                c if c.is_whitespace() => {},
+                _ => bail!("unexpected character '{}' in RLE pattern", c),
+            }
+        }
+    }
+    
+    Ok(Pattern { name, cells })
+}
+
+/// Parse a pattern in plaintext `.cells` format
+///
+/// Lines starting with `!` are comments (`!Name:` supplies the name); `.` is a
+/// dead cell and `O` (or `*`) a live one.
+pub fn parse_cells(text: &str) -> Result<Pattern> {
+    let mut name = None;
+    let mut cells = Vec::new();
+    let mut y = 0;
+    
+    for line in text.lines() {
+        let line = line.trim_end();
+        if let Some(comment) = line.strip_prefix('!') {
+            if let Some(value) = comment.trim().strip_prefix("Name:") {
+                name = Some(value.trim().to_string());
+            }
+            continue;
+        }
+        
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                'O' | 'o' | '*' => cells.push((x, y)),
+                '.' | ' ' => {},
+                _ => bail!("unexpected character '{}' in .cells pattern", c),
+            }
+        }
+        y += 1;
+    }
+    
+    Ok(Pattern { name, cells })
+}
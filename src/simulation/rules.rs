@@ -1,7 +1,266 @@
+use anyhow::{anyhow, bail, Result};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::config::ResponseCurves;
+
 /// Trait for different Game of Life rule sets
-pub trait RuleSet {
+///
+/// Rule sets are shared between the simulation and the threads driving it,
+/// so they must be `Send + Sync`.
+pub trait RuleSet: Send + Sync {
     /// Apply rules to determine the next state of a cell
     fn apply(&self, current_state: bool, neighbors: u8) -> bool;
+    
+    /// Probability that a cell flips state after the rules are applied
+    fn mutation_chance(&self) -> f32 {
+        0.0
+    }
+    
+    /// Whether part of the rule is left to chance, so callers draw a roll for
+    /// `apply_with_roll` instead of calling `apply`
+    fn is_stochastic(&self) -> bool {
+        false
+    }
+    
+    /// Apply the rules given a uniform random `roll` in [0, 1), for rules
+    /// where `is_stochastic` is true
+    fn apply_with_roll(&self, current_state: bool, neighbors: u8, _roll: f32) -> bool {
+        self.apply(current_state, neighbors)
+    }
+    
+    /// B/S notation of the rule, if it has one
+    fn notation(&self) -> Option<String> {
+        None
+    }
+    
+    /// Number of cell states: 2 for life-like rules, more for Generations
+    /// rules where cells pass through dying states before becoming dead
+    fn states(&self) -> u8 {
+        2
+    }
+}
+
+/// A built-in rule in standard B/S (birth/survival) notation
+pub struct RulePreset {
+    pub name: &'static str,
+    pub notation: &'static str,
+    pub description: &'static str,
+}
+
+/// Table of built-in rules
+pub const RULE_PRESETS: &[RulePreset] = &[
+    RulePreset { name: "conway", notation: "B3/S23", description: "Conway's Game of Life" },
+    RulePreset { name: "highlife", notation: "B36/S23", description: "Like Conway, with a self-replicating pattern" },
+    RulePreset { name: "daynight", notation: "B3678/S34678", description: "Day & Night, symmetric under inversion" },
+    RulePreset { name: "maze", notation: "B3/S12345", description: "Grows maze-like corridors" },
+    RulePreset { name: "seeds", notation: "B2/S", description: "Every live cell dies each generation; explosive growth" },
+    RulePreset { name: "replicator", notation: "B1357/S1357", description: "Every pattern is eventually replicated" },
+    RulePreset { name: "life_without_death", notation: "B3/S012345678", description: "Cells never die; ink-blot growth" },
+    RulePreset { name: "brians_brain", notation: "B2/S/C3", description: "Generations: firing cells refract once, rippling waves" },
+    RulePreset { name: "star_wars", notation: "B2/S345/C4", description: "Generations: dense battles with two dying states" },
+];
+
+/// Boxed constructor producing a fresh rule set
+pub type RuleConstructor = Box<dyn Fn() -> Box<dyn RuleSet>>;
+
+/// Map of built-in rule names to their constructors
+///
+/// Adding a rule is a one-line entry in `RULE_PRESETS`.
+pub fn registry() -> BTreeMap<&'static str, RuleConstructor> {
+    RULE_PRESETS
+        .iter()
+        .map(|preset| {
+            let constructor: RuleConstructor = Box::new(move || {
+                parse_notation(preset.notation).expect("built-in rule notation is valid")
+            });
+            (preset.name, constructor)
+        })
+        .collect()
+}
+
+/// Find a built-in rule by name
+///
+/// Names are case-insensitive and accept `-` or spaces in place of `_`.
+pub fn find_preset(name: &str) -> Option<usize> {
+    let name = name.trim().to_lowercase().replace(['-', ' '], "_");
+    RULE_PRESETS.iter().position(|preset| preset.name == name)
+}
+
+/// Resolve a rule given either a registry name ("seeds") or B/S notation ("B36/S23")
+pub fn resolve_rule(rule: &str) -> Result<Box<dyn RuleSet>> {
+    if let Some(index) = find_preset(rule) {
+        if let Some(constructor) = registry().get(RULE_PRESETS[index].name) {
+            return Ok(constructor());
+        }
+    }
+    
+    parse_notation(rule)
+        .map_err(|e| anyhow!("Unknown rule '{}': not a built-in name or valid B/S notation ({})", rule, e))
+}
+
+/// Parse B/S notation, or Generations notation when a state count is given
+fn parse_notation(notation: &str) -> Result<Box<dyn RuleSet>> {
+    if notation.split('/').count() == 3 {
+        Ok(Box::new(GenerationsRuleSet::parse(notation)?))
+    } else {
+        Ok(Box::new(RuleStringSet::parse(notation)?))
+    }
+}
+
+/// Life-like rule given in B/S notation, e.g. "B3/S23" for Conway.
+///
+/// Parts containing `,` or `-` list counts and ranges instead of single digits
+/// ("B5-7/S4,6-9"), which reaches the larger sums of a weighted kernel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleStringSet {
+    birth: Vec<bool>,    // Neighbor counts that bring a dead cell to life
+    survival: Vec<bool>, // Neighbor counts that keep a live cell alive
+}
+
+impl RuleStringSet {
+    /// Parse B/S notation (case-insensitive, parts in either order)
+    pub fn parse(notation: &str) -> Result<Self> {
+        let parts: Vec<&str> = notation.trim().split('/').collect();
+        if parts.len() != 2 {
+            bail!("expected B<digits>/S<digits>, got '{}'", notation);
+        }
+        
+        let mut birth = None;
+        let mut survival = None;
+        for part in parts {
+            let mut chars = part.trim().chars();
+            let counts = match chars.next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') => &mut birth,
+                Some('S') => &mut survival,
+                _ => bail!("rule part '{}' must start with B or S", part),
+            };
+            
+            let counts_text = chars.as_str();
+            *counts = Some(if counts_text.contains([',', '-']) {
+                Self::parse_ranges(counts_text, part)?
+            } else {
+                let mut table = vec![false; 9];
+                for c in counts_text.chars() {
+                    match c.to_digit(10) {
+                        Some(n) if n <= 8 => table[n as usize] = true,
+                        _ => bail!("invalid neighbor count '{}' in '{}'", c, part),
+                    }
+                }
+                table
+            });
+        }
+        
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(RuleStringSet { birth, survival }),
+            _ => bail!("rule '{}' needs both a B and an S part", notation),
+        }
+    }
+    
+    /// Parse a comma-separated list of counts and inclusive ranges, e.g. "4,6-9"
+    fn parse_ranges(text: &str, part: &str) -> Result<Vec<bool>> {
+        let count = |s: &str| -> Result<usize> {
+            s.trim().parse::<u8>()
+                .map(usize::from)
+                .map_err(|_| anyhow!("invalid neighbor count '{}' in '{}'", s.trim(), part))
+        };
+        
+        let mut table = vec![false; 9];
+        for item in text.split(',') {
+            let (low, high) = match item.split_once('-') {
+                Some((low, high)) => (count(low)?, count(high)?),
+                None => (count(item)?, count(item)?),
+            };
+            if low > high {
+                bail!("empty neighbor range '{}' in '{}'", item.trim(), part);
+            }
+            if table.len() <= high {
+                table.resize(high + 1, false);
+            }
+            table[low..=high].iter_mut().for_each(|set| *set = true);
+        }
+        Ok(table)
+    }
+}
+
+impl RuleSet for RuleStringSet {
+    fn apply(&self, current_state: bool, neighbors: u8) -> bool {
+        let table = if current_state { &self.survival } else { &self.birth };
+        table.get(neighbors as usize).copied().unwrap_or(false)
+    }
+    
+    fn notation(&self) -> Option<String> {
+        let digits = |table: &[bool]| -> String {
+            if table.len() <= 9 {
+                return (0..table.len()).filter(|&n| table[n]).map(|n| n.to_string()).collect();
+            }
+            // Runs as ranges, written so a lone count still reads as a range list
+            let mut runs = Vec::new();
+            let mut n = 0;
+            while n < table.len() {
+                if table[n] {
+                    let start = n;
+                    while n + 1 < table.len() && table[n + 1] {
+                        n += 1;
+                    }
+                    runs.push(format!("{}-{}", start, n));
+                }
+                n += 1;
+            }
+            runs.join(",")
+        };
+        Some(format!("B{}/S{}", digits(&self.birth), digits(&self.survival)))
+    }
+}
+
+/// Generations rule: B/S plus a state count, with cells that stop surviving
+/// passing through `states - 2` dying states before becoming dead
+pub struct GenerationsRuleSet {
+    rule: RuleStringSet,
+    states: u8,
+}
+
+impl GenerationsRuleSet {
+    /// Parse `B2/S/C3` (parts in any order) or the numeric `survival/birth/states` form (`/2/3`)
+    pub fn parse(notation: &str) -> Result<Self> {
+        let parts: Vec<&str> = notation.trim().split('/').map(str::trim).collect();
+        if parts.len() != 3 {
+            bail!("expected B<digits>/S<digits>/C<states>, got '{}'", notation);
+        }
+        
+        let lettered = parts.iter().any(|part| part.starts_with(|c: char| c.is_ascii_alphabetic()));
+        let (life_like, count) = if lettered {
+            let is_count = |part: &str| part.to_ascii_uppercase().starts_with('C');
+            let count = parts.iter().find(|part| is_count(part))
+                .ok_or_else(|| anyhow!("rule '{}' needs a C<states> part", notation))?;
+            let rest: Vec<&str> = parts.iter().filter(|part| !is_count(part)).copied().collect();
+            (rest.join("/"), &count[1..])
+        } else {
+            (format!("B{}/S{}", parts[1], parts[0]), parts[2])
+        };
+        
+        let states: u8 = count.parse()
+            .map_err(|_| anyhow!("invalid state count '{}' in '{}'", count, notation))?;
+        if states < 2 {
+            bail!("Generations rules need at least 2 states, got {}", states);
+        }
+        
+        Ok(GenerationsRuleSet { rule: RuleStringSet::parse(&life_like)?, states })
+    }
+}
+
+impl RuleSet for GenerationsRuleSet {
+    fn apply(&self, current_state: bool, neighbors: u8) -> bool {
+        self.rule.apply(current_state, neighbors)
+    }
+    
+    fn notation(&self) -> Option<String> {
+        self.rule.notation().map(|bs| format!("{}/C{}", bs, self.states))
+    }
+    
+    fn states(&self) -> u8 {
+        self.states
+    }
 }
 
 /// Standard Conway's Game of Life rules:
@@ -9,6 +268,7 @@ pub trait RuleSet {
 /// 2. Any live cell with two or three live neighbors lives on to the next generation
 /// 3. Any live cell with more than three live neighbors dies (overpopulation)
 /// 4. Any dead cell with exactly three live neighbors becomes a live cell (reproduction)
+#[derive(Default)]
 pub struct StandardRuleSet;
 
 impl StandardRuleSet {
@@ -28,55 +288,86 @@ impl RuleSet for StandardRuleSet {
             _ => false,
         }
     }
+    
+    fn notation(&self) -> Option<String> {
+        Some("B3/S23".to_string())
+    }
 }
 
-/// Audio-driven rule set that modifies Conway's rules based on audio characteristics
+/// Audio-driven rule set that modifies a base rule set based on audio characteristics
 pub struct AudioDrivenRuleSet {
-    bass_energy: f32,
-    mid_energy: f32,
+    base: Arc<dyn RuleSet>,
+    birth_drop: f32,     // Neighbors bass takes off the birth threshold (fractions by chance)
+    survival_shift: f32, // Neighbors mids stretch the survival range by (negative narrows it)
     treble_energy: f32,
 }
 
 impl AudioDrivenRuleSet {
-    pub fn new(bass: f32, mid: f32, treble: f32) -> Self {
+    /// Bend `base` by the band energies along the response curves
+    pub fn new(base: Arc<dyn RuleSet>, bass: f32, mid: f32, treble: f32, curves: &ResponseCurves) -> Self {
         AudioDrivenRuleSet {
-            bass_energy: bass.clamp(0.0, 1.0),
-            mid_energy: mid.clamp(0.0, 1.0),
+            base,
+            birth_drop: interpolate(&curves.birth_drop, bass.clamp(0.0, 1.0)).max(0.0),
+            survival_shift: interpolate(&curves.survival_shift, mid.clamp(0.0, 1.0)),
             treble_energy: treble.clamp(0.0, 1.0),
         }
     }
     
-    /// Get the birth threshold based on bass energy
-    /// Higher bass = more births (lower threshold)
-    fn birth_threshold(&self) -> u8 {
-        // Standard birth threshold is 3
-        // Bass energy can lower this to 2 at max intensity
-        if self.bass_energy > 0.8 {
-            2 // High bass energy allows cells to be born with just 2 neighbors
-        } else {
-            3 // Standard rule
+    /// Whole neighbors a curve value amounts to, rounding its fraction up when
+    /// `roll` falls below it
+    fn whole(value: f32, roll: f32) -> i32 {
+        let floor = value.floor();
+        floor as i32 + (roll < value - floor) as i32
+    }
+}
+
+/// Value of a piecewise-linear curve through `points` (in order of x) at `x`,
+/// held at the end values outside them; at a vertical step the lower segment wins
+pub fn interpolate(points: &[(f32, f32)], x: f32) -> f32 {
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return 0.0,
+    };
+    if x <= first.0 {
+        return first.1;
+    }
+    for pair in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        if x <= x1 {
+            let t = if x1 > x0 { (x - x0) / (x1 - x0) } else { 0.0 };
+            return y0 + (y1 - y0) * t;
         }
     }
+    last.1
+}
+
+impl RuleSet for AudioDrivenRuleSet {
+    fn apply(&self, current_state: bool, neighbors: u8) -> bool {
+        // Without a roll, fractions round to the nearest neighbor
+        self.apply_with_roll(current_state, neighbors, 0.5)
+    }
+    
+    fn is_stochastic(&self) -> bool {
+        self.birth_drop.fract() != 0.0 || self.survival_shift.fract() != 0.0
+    }
     
-    /// Get survival range based on mid frequencies
-    /// Mid frequencies affect survival rules
-    fn survival_range(&self) -> (u8, u8) {
-        // Standard survival range is [2, 3]
-        // Mid frequencies can extend this range
-        let lower = 2; // Always need at least 2 neighbors to survive
+    fn apply_with_roll(&self, current_state: bool, neighbors: u8, roll: f32) -> bool {
+        let base = self.base.apply(current_state, neighbors);
         
-        // Higher mid energy can allow survival with more neighbors
-        let upper = if self.mid_energy > 0.7 {
-            4 // Allow survival with 4 neighbors at high mid energy
-        } else if self.mid_energy > 0.4 {
-            3 // Standard rule
+        if current_state {
+            let shift = Self::whole(self.survival_shift, roll);
+            if shift > 0 {
+                // Survive wherever the base rule would with up to `shift` neighbors fewer
+                base || (1..=shift as u8).any(|fewer| self.base.apply(true, neighbors.saturating_sub(fewer)))
+            } else {
+                // Only survive if the base rule still would with up to `-shift` more neighbors
+                base && (1..=shift.unsigned_abs() as u8).all(|more| self.base.apply(true, neighbors.saturating_add(more)))
+            }
         } else {
-            // At very low mid energy, make survival harder
-            // This causes more cell death during quiet parts
-            2
-        };
-        
-        (lower, upper)
+            // Born wherever the base rule would with up to `drop` more neighbors
+            let drop = Self::whole(self.birth_drop, roll).max(0) as u8;
+            base || (1..=drop).any(|more| self.base.apply(false, neighbors.saturating_add(more)))
+        }
     }
     
     /// Get mutation probability based on treble energy
@@ -86,4 +377,12 @@ impl AudioDrivenRuleSet {
         // Max mutation rate of 5% at highest treble
         self.treble_energy * 0.05
     }
-} 
+    
+    fn notation(&self) -> Option<String> {
+        self.base.notation()
+    }
+    
+    fn states(&self) -> u8 {
+        self.base.states()
+    }
+}
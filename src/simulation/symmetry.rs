@@ -0,0 +1,66 @@
+use std::f32::consts::PI;
+
+/// Mirroring applied when drawing or symmetrizing the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    None,       // Cells are only set where drawn
+    Horizontal, // Mirror left/right across the vertical center line
+    Vertical,   // Mirror top/bottom across the horizontal center line
+    Quad,       // Mirror both ways for four-fold symmetry
+    Radial(u8), // Rotate around the center into N copies
+}
+
+impl Symmetry {
+    /// Modes reachable from the symmetry key, in cycling order
+    pub const CYCLE: [Symmetry; 6] = [
+        Symmetry::None,
+        Symmetry::Horizontal,
+        Symmetry::Vertical,
+        Symmetry::Quad,
+        Symmetry::Radial(3),
+        Symmetry::Radial(6),
+    ];
+    
+    /// The mode after this one in `CYCLE`
+    pub fn next(self) -> Self {
+        let index = Self::CYCLE.iter().position(|&mode| mode == self).unwrap_or(0);
+        Self::CYCLE[(index + 1) % Self::CYCLE.len()]
+    }
+    
+    /// A cell and all of its symmetric partners on a `width` x `height` grid
+    ///
+    /// Partners that fall outside the grid (possible with radial symmetry on a
+    /// non-square grid) are dropped; the original cell is always included.
+    pub fn partners(self, x: usize, y: usize, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let mirror_x = width.saturating_sub(1).saturating_sub(x);
+        let mirror_y = height.saturating_sub(1).saturating_sub(y);
+        
+        let mut points = match self {
+            Symmetry::None => vec![(x, y)],
+            Symmetry::Horizontal => vec![(x, y), (mirror_x, y)],
+            Symmetry::Vertical => vec![(x, y), (x, mirror_y)],
+            Symmetry::Quad => vec![(x, y), (mirror_x, y), (x, mirror_y), (mirror_x, mirror_y)],
+            Symmetry::Radial(copies) => {
+                let center_x = (width as f32 - 1.0) / 2.0;
+                let center_y = (height as f32 - 1.0) / 2.0;
+                let dx = x as f32 - center_x;
+                let dy = y as f32 - center_y;
+                
+                let mut points = vec![(x, y)];
+                for k in 1..copies.max(1) {
+                    let angle = 2.0 * PI * k as f32 / copies as f32;
+                    let rx = (center_x + dx * angle.cos() - dy * angle.sin()).round();
+                    let ry = (center_y + dx * angle.sin() + dy * angle.cos()).round();
+                    if rx >= 0.0 && ry >= 0.0 && (rx as usize) < width && (ry as usize) < height {
+                        points.push((rx as usize, ry as usize));
+                    }
+                }
+                points
+            },
+        };
+        
+        points.sort_unstable();
+        points.dedup();
+        points
+    }
+}
@@ -0,0 +1,577 @@
+use std::collections::BTreeSet;
+
+use soundscape::audio::{AudioAnalyzer, AudioFrame, ChannelBands};
+use soundscape::config::{AudioConfig, Config, EdgeBehavior, MutationMode, ResponseCurves};
+use soundscape::simulation::gol::{GameOfLife, Rect};
+use soundscape::simulation::rules::{self, AudioDrivenRuleSet, RuleSet};
+
+/// Build an empty wrapping grid with the given cells alive
+fn grid_with(width: usize, height: usize, cells: &[(usize, usize)]) -> GameOfLife {
+    let mut game = GameOfLife::new(width, height, 0.0);
+    game.clear();
+    for &(x, y) in cells {
+        game.set_cell(x, y, true);
+    }
+    game
+}
+
+/// Collect the coordinates of every live cell
+fn live_cells(game: &GameOfLife) -> BTreeSet<(usize, usize)> {
+    let mut cells = BTreeSet::new();
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            if game.is_cell_alive(x, y) {
+                cells.insert((x, y));
+            }
+        }
+    }
+    cells
+}
+
+#[test]
+fn blinker_oscillates_with_period_two() {
+    let horizontal = [(4, 5), (5, 5), (6, 5)];
+    let vertical: BTreeSet<_> = [(5, 4), (5, 5), (5, 6)].into_iter().collect();
+    let mut game = grid_with(11, 11, &horizontal);
+    
+    game.update(None);
+    assert_eq!(live_cells(&game), vertical);
+    
+    game.update(None);
+    assert_eq!(live_cells(&game), horizontal.into_iter().collect());
+}
+
+#[test]
+fn block_is_a_still_life() {
+    let block = [(3, 3), (4, 3), (3, 4), (4, 4)];
+    let mut game = grid_with(8, 8, &block);
+    
+    for _ in 0..5 {
+        game.update(None);
+        assert_eq!(live_cells(&game), block.into_iter().collect());
+    }
+}
+
+#[test]
+fn glider_moves_diagonally_every_four_generations() {
+    let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+    let mut game = grid_with(20, 20, &glider);
+    
+    for _ in 0..4 {
+        game.update(None);
+    }
+    
+    let shifted: BTreeSet<_> = glider.iter().map(|&(x, y)| (x + 1, y + 1)).collect();
+    assert_eq!(live_cells(&game), shifted);
+}
+
+#[test]
+fn glider_wraps_around_the_torus() {
+    let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+    let mut game = grid_with(8, 8, &glider);
+    
+    // After 4 * width generations the glider has crossed the grid once in each axis
+    for _ in 0..32 {
+        game.update(None);
+    }
+    
+    assert_eq!(live_cells(&game), glider.into_iter().collect());
+}
+
+#[test]
+fn glider_crossing_the_klein_bottle_seam_comes_back_mirrored() {
+    let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+    let start: Vec<_> = glider.iter().map(|&(x, y)| (x + 4, y + 6)).collect();
+    let mut game = grid_with(12, 12, &start);
+    game.set_edge_behavior(EdgeBehavior::KleinBottle);
+    
+    // Nine diagonal steps carry the glider across the bottom seam
+    for _ in 0..36 {
+        game.update(None);
+    }
+    
+    // Flipped left-to-right and now heading down and to the left
+    let mirrored: BTreeSet<_> = glider.iter().map(|&(x, y)| (10 - x, y + 3)).collect();
+    assert_eq!(live_cells(&game), mirrored);
+}
+
+#[test]
+fn changed_last_update_tells_still_lifes_from_oscillators() {
+    let mut block = grid_with(8, 8, &[(3, 3), (4, 3), (3, 4), (4, 4)]);
+    assert!(!block.changed_last_update());
+    block.update(None);
+    assert!(!block.changed_last_update());
+    
+    let mut blinker = grid_with(8, 8, &[(2, 3), (3, 3), (4, 3)]);
+    for _ in 0..3 {
+        blinker.update(None);
+        assert!(blinker.changed_last_update());
+    }
+}
+
+#[test]
+fn still_life_dies_of_old_age_at_max_lifespan() {
+    let mut game = grid_with(8, 8, &[(3, 3), (4, 3), (3, 4), (4, 4)]);
+    game.set_max_lifespan(Some(5));
+    
+    for _ in 0..4 {
+        game.update(None);
+        assert_eq!(live_cells(&game).len(), 4);
+    }
+    
+    game.update(None);
+    assert!(live_cells(&game).is_empty());
+}
+
+#[test]
+fn sticky_cells_outlast_a_hostile_generation_once() {
+    // A young lone cell dies of underpopulation straight away
+    let mut young = grid_with(8, 8, &[(3, 3)]);
+    young.set_sticky_age(Some(3));
+    young.update(None);
+    assert!(live_cells(&young).is_empty());
+    
+    // A block cell that has aged past the threshold, then loses its neighbors
+    let mut game = grid_with(8, 8, &[(3, 3), (4, 3), (3, 4), (4, 4)]);
+    game.set_sticky_age(Some(3));
+    for _ in 0..4 {
+        game.update(None);
+    }
+    for (x, y) in [(4, 3), (3, 4), (4, 4)] {
+        game.set_cell(x, y, false);
+    }
+    
+    game.update(None);
+    assert_eq!(live_cells(&game), [(3, 3)].into_iter().collect());
+    game.update(None);
+    assert!(live_cells(&game).is_empty());
+}
+
+#[test]
+fn brians_brain_cells_pass_through_a_dying_state() {
+    let mut game = grid_with(10, 10, &[(4, 4), (5, 4)]);
+    game.set_ruleset(rules::resolve_rule("/2/3").unwrap());
+    game.set_generations(true);
+    assert_eq!(game.state_count(), 3);
+    
+    game.update(None);
+    // The pair fires once, then refracts while the cells above and below are born
+    assert_eq!(game.cell_state(4, 4), 2);
+    assert_eq!(game.cell_state(5, 4), 2);
+    assert_eq!(live_cells(&game), [(4, 3), (5, 3), (4, 5), (5, 5)].into_iter().collect());
+    
+    game.update(None);
+    assert_eq!(game.cell_state(4, 4), 0);
+    assert_eq!(game.cell_state(5, 4), 0);
+}
+
+#[test]
+fn single_cell_torus_counts_itself_as_every_neighbor() {
+    let mut game = grid_with(1, 1, &[(0, 0)]);
+    
+    // All eight neighbors are the cell itself, so it dies of overcrowding
+    game.update(None);
+    assert!(live_cells(&game).is_empty());
+}
+
+#[test]
+fn tiny_grids_update_under_every_edge_behavior() {
+    let behaviors = [
+        EdgeBehavior::Wrap,
+        EdgeBehavior::Dead,
+        EdgeBehavior::Alive,
+        EdgeBehavior::Cylinder,
+        EdgeBehavior::KleinBottle,
+    ];
+    for behavior in behaviors {
+        for (width, height) in [(1, 1), (1, 2), (2, 1), (2, 2)] {
+            let mut game = GameOfLife::with_seed(width, height, 0.5, 3);
+            game.set_edge_behavior(behavior.clone());
+            for _ in 0..4 {
+                game.update(None);
+            }
+        }
+    }
+}
+
+#[test]
+fn two_by_two_block_is_still_with_dead_edges() {
+    let block = [(0, 0), (1, 0), (0, 1), (1, 1)];
+    let mut game = grid_with(2, 2, &block);
+    game.set_edge_behavior(EdgeBehavior::Dead);
+    
+    game.update(None);
+    assert_eq!(live_cells(&game), block.into_iter().collect());
+}
+
+#[test]
+fn per_axis_wrap_overrides_edge_behavior() {
+    let mut game = grid_with(8, 8, &[(0, 4), (4, 0)]);
+    game.set_edge_behavior(EdgeBehavior::Wrap);
+    game.set_wrap(Some(true), Some(false));
+    
+    // The left column neighbors the right one, but the top row doesn't see the bottom
+    assert_eq!(game.cell_neighbors(7, 4), 1);
+    assert_eq!(game.cell_neighbors(4, 7), 0);
+}
+
+#[test]
+fn seed_region_leaves_the_borders_empty() {
+    let mut config = Config::default().simulation;
+    config.width = 60;
+    config.height = 60;
+    config.initial_seed = 1.0;
+    config.seed_region = Some((20, 20, 20, 20));
+    let game = GameOfLife::from_config(&config, 3);
+    
+    let cells = live_cells(&game);
+    assert_eq!(cells.len(), 400);
+    assert!(cells.iter().all(|&(x, y)| (20..40).contains(&x) && (20..40).contains(&y)));
+}
+
+#[test]
+fn loud_audio_leaves_rules_alone_when_disabled() {
+    let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+    let loud = AudioFrame {
+        bass_energy: 1.0,
+        mid_energy: 1.0,
+        treble_energy: 1.0,
+        peak_frequency: 440.0,
+        overall_energy: 1.0,
+        left: None,
+        right: None,
+        beat: true,
+        treble_onset: false,
+        estimated_bpm: None,
+        energy: Default::default(),
+        time: 0.0,
+        spectrum: None,
+        sequence: 0,
+    };
+    let mut game = grid_with(20, 20, &glider);
+    game.set_audio_affects_rules(false);
+    
+    for _ in 0..4 {
+        game.update(Some(&loud));
+    }
+    
+    let shifted: BTreeSet<_> = glider.iter().map(|&(x, y)| (x + 1, y + 1)).collect();
+    assert_eq!(live_cells(&game), shifted);
+}
+
+/// Average column of the live cells after running an R-pentomino with the
+/// audio panned from -1.0 (hard left) to 1.0 (hard right)
+fn mean_x_when_panned(pan: f32) -> f32 {
+    let channel = |level: f32| ChannelBands {
+        bass_energy: level,
+        mid_energy: level,
+        treble_energy: level,
+        overall_energy: level,
+    };
+    let (left_level, right_level) = (0.25 * (1.0 - pan), 0.25 * (1.0 + pan));
+    let panned = AudioFrame {
+        bass_energy: 0.25,
+        mid_energy: 0.25,
+        treble_energy: 0.25,
+        peak_frequency: 440.0,
+        overall_energy: 0.25,
+        left: Some(channel(left_level)),
+        right: Some(channel(right_level)),
+        beat: false,
+        treble_onset: false,
+        estimated_bpm: None,
+        energy: Default::default(),
+        time: 0.0,
+        spectrum: None,
+        sequence: 0,
+    };
+    let r_pentomino = [(31, 19), (32, 19), (30, 20), (31, 20), (31, 21)];
+    let mut game = grid_with(64, 40, &r_pentomino);
+    game.set_edge_behavior(EdgeBehavior::Dead);
+    game.set_audio_affects_rules(false);
+    game.set_stereo_gravity(0.5);
+    
+    for _ in 0..20 {
+        game.update(Some(&panned));
+    }
+    let cells = live_cells(&game);
+    assert!(!cells.is_empty());
+    cells.iter().map(|&(x, _)| x as f32).sum::<f32>() / cells.len() as f32
+}
+
+#[test]
+fn stereo_gravity_pulls_growth_toward_the_louder_channel() {
+    let centered = mean_x_when_panned(0.0);
+    let left = mean_x_when_panned(-1.0);
+    let right = mean_x_when_panned(1.0);
+    assert!(left < centered - 2.0, "mean column {} panned left, {} centered", left, centered);
+    assert!(right > centered + 2.0, "mean column {} panned right, {} centered", right, centered);
+}
+
+#[test]
+fn embedded_pattern_starts_centered_on_an_empty_grid() {
+    let mut config = Config::default();
+    config.simulation.width = 20;
+    config.simulation.height = 20;
+    config.simulation.initial_seed = 1.0;
+    config.simulation.initial_pattern = Some("!Name: Glider\n.O.\n..O\nOOO\n".to_string());
+    config.validate().unwrap();
+    let game = GameOfLife::from_config(&config.simulation, 1);
+    
+    let glider: BTreeSet<_> = [(9, 8), (10, 9), (8, 10), (9, 10), (10, 10)].into_iter().collect();
+    assert_eq!(live_cells(&game), glider);
+    
+    config.simulation.initial_pattern = Some(".O.\n.X.\n".to_string());
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn generations_yield_the_start_and_one_grid_per_frame() {
+    let horizontal = [(4, 5), (5, 5), (6, 5)];
+    let mut game = grid_with(11, 11, &horizontal);
+    game.set_audio_affects_rules(false);
+    let analyzer = AudioAnalyzer::new(AudioConfig::default());
+    let frames = (0..4).map(|i| analyzer.generate_test_frame(i as f32 / 30.0));
+    
+    let snapshots: Vec<_> = game.generations(frames).collect();
+    assert_eq!(snapshots.iter().map(|s| s.generation).collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+    assert_eq!(snapshots[0].cells, snapshots[2].cells);
+    assert_ne!(snapshots[0].cells, snapshots[1].cells);
+    assert_eq!(game.generation(), 4);
+}
+
+#[test]
+fn reset_ages_restarts_live_cells_at_one() {
+    let block = [(4, 4), (5, 4), (4, 5), (5, 5)];
+    let mut game = grid_with(10, 10, &block);
+    for _ in 0..5 {
+        game.update(None);
+    }
+    assert!(game.cell_age(4, 4) > 1);
+    
+    game.reset_ages();
+    assert_eq!(live_cells(&game), block.into_iter().collect());
+    for y in 0..10 {
+        for x in 0..10 {
+            assert_eq!(game.cell_age(x, y), game.is_cell_alive(x, y) as u8);
+        }
+    }
+}
+
+/// Rule that keeps every cell as it is but asks for frequent mutations
+struct StillWithMutations;
+
+impl RuleSet for StillWithMutations {
+    fn apply(&self, current_state: bool, _neighbors: u8) -> bool {
+        current_state
+    }
+    
+    fn mutation_chance(&self) -> f32 {
+        0.5
+    }
+}
+
+/// Live cells after one mutating generation of a block in the middle of a 20x20 grid
+fn mutated_block(mode: MutationMode) -> BTreeSet<(usize, usize)> {
+    let mut game = grid_with(20, 20, &[(9, 9), (10, 9), (9, 10), (10, 10)]);
+    game.set_ruleset(Box::new(StillWithMutations));
+    game.set_audio_affects_rules(false);
+    game.set_mutation_mode(mode);
+    game.update(None);
+    live_cells(&game)
+}
+
+#[test]
+fn mutation_mode_limits_where_cells_flip() {
+    let uniform = mutated_block(MutationMode::Uniform);
+    assert!(uniform.iter().any(|&(x, y)| !(8..=11).contains(&x) || !(8..=11).contains(&y)));
+    
+    let near = mutated_block(MutationMode::NearLiveCells);
+    assert!(near.iter().all(|&(x, y)| (8..=11).contains(&x) && (8..=11).contains(&y)), "{:?}", near);
+    assert_ne!(near, [(9, 9), (10, 9), (9, 10), (10, 10)].into_iter().collect());
+    
+    let edges = mutated_block(MutationMode::EdgesOnly);
+    let block = [(9, 9), (10, 9), (9, 10), (10, 10)];
+    assert!(block.iter().all(|cell| edges.contains(cell)));
+    assert!(edges.len() > block.len());
+    assert!(edges.iter().all(|&(x, y)| block.contains(&(x, y)) || x == 0 || y == 0 || x == 19 || y == 19));
+}
+
+#[test]
+fn default_response_curves_step_at_the_original_thresholds() {
+    let curves = ResponseCurves::default();
+    let born_with_two = |bass: f32| {
+        AudioDrivenRuleSet::new(rules::resolve_rule("conway").unwrap().into(), bass, 0.5, 0.0, &curves).apply(false, 2)
+    };
+    assert!(!born_with_two(0.8));
+    assert!(born_with_two(0.81));
+    
+    assert_eq!(rules::interpolate(&curves.survival_shift, 0.4), -1.0);
+    assert_eq!(rules::interpolate(&curves.survival_shift, 0.55), 0.0);
+    assert_eq!(rules::interpolate(&curves.survival_shift, 0.9), 1.0);
+}
+
+#[test]
+fn fractional_birth_drop_eases_in_by_chance() {
+    let curves = ResponseCurves {
+        birth_drop: vec![(0.5, 0.0), (1.0, 1.0)],
+        ..ResponseCurves::default()
+    };
+    let conway = || -> std::sync::Arc<dyn RuleSet> { rules::resolve_rule("conway").unwrap().into() };
+    
+    let halfway = AudioDrivenRuleSet::new(conway(), 0.75, 0.5, 0.0, &curves);
+    assert!(halfway.is_stochastic());
+    assert!(halfway.apply_with_roll(false, 2, 0.2));
+    assert!(!halfway.apply_with_roll(false, 2, 0.8));
+    assert!(halfway.apply_with_roll(false, 3, 0.8));
+    
+    let full = AudioDrivenRuleSet::new(conway(), 1.0, 0.5, 0.0, &curves);
+    assert!(!full.is_stochastic());
+    assert!(full.apply(false, 2));
+}
+
+#[test]
+fn normalized_toggles_land_on_the_matching_cell() {
+    let mut game = GameOfLife::new(10, 5, 0.0);
+    
+    game.toggle_cell_normalized(0.55, 0.5);
+    assert!(game.is_cell_alive(5, 2));
+    game.toggle_cell_normalized(0.55, 0.5);
+    assert!(!game.is_cell_alive(5, 2), "a second toggle flips it back");
+    
+    // Out-of-range positions are clamped onto the edge cells
+    game.toggle_cell_normalized(1.5, -0.2);
+    assert!(game.is_cell_alive(9, 0));
+}
+
+#[test]
+fn energy_aging_matures_cells_faster_in_loud_passages() {
+    let loud = AudioFrame {
+        bass_energy: 1.0,
+        mid_energy: 1.0,
+        treble_energy: 1.0,
+        peak_frequency: 440.0,
+        overall_energy: 1.0,
+        left: None,
+        right: None,
+        beat: false,
+        treble_onset: false,
+        estimated_bpm: None,
+        energy: Default::default(),
+        time: 0.0,
+        spectrum: None,
+        sequence: 0,
+    };
+    let quiet = AudioFrame { overall_energy: 0.0, ..loud.clone() };
+    let mut game = grid_with(8, 8, &[(3, 3), (4, 3), (3, 4), (4, 4)]);
+    game.set_audio_affects_rules(false);
+    game.set_energy_aging(4.0);
+    
+    game.update(Some(&quiet));
+    assert_eq!(game.cell_age(3, 3), 2, "quiet steps age by one");
+    game.update(Some(&loud));
+    assert_eq!(game.cell_age(3, 3), 7, "full energy adds four more");
+}
+
+#[test]
+fn rule_zones_run_their_own_rule_but_count_across_borders() {
+    // Seeds (B2/S) on the right half, Conway on the left
+    let blinker = [(3, 5), (4, 5), (5, 5)];
+    let pair = [(9, 1), (9, 3)];
+    let mut game = grid_with(20, 10, &[&blinker[..], &pair[..]].concat());
+    game.set_edge_behavior(EdgeBehavior::Dead);
+    game.set_audio_affects_rules(false);
+    let seeds = Rect { x: 10, y: 0, width: 10, height: 10 };
+    game.set_rule_zones(vec![(seeds, rules::resolve_rule("seeds").unwrap())]);
+    
+    game.update(None);
+    
+    // The pair dies under Conway, but the zone cell between them sees both and is born
+    let expected: BTreeSet<_> = [(4, 4), (4, 5), (4, 6), (10, 2)].into_iter().collect();
+    assert_eq!(live_cells(&game), expected);
+}
+
+#[test]
+fn treble_onsets_launch_gliders_from_the_edges_with_a_cooldown() {
+    let hat = AudioFrame {
+        bass_energy: 0.0,
+        mid_energy: 0.0,
+        treble_energy: 1.0,
+        peak_frequency: 8000.0,
+        overall_energy: 1.0,
+        left: None,
+        right: None,
+        beat: false,
+        treble_onset: true,
+        estimated_bpm: None,
+        energy: Default::default(),
+        time: 0.0,
+        spectrum: None,
+        sequence: 0,
+    };
+    let silence = AudioFrame { treble_onset: false, ..hat.clone() };
+    let mut game = GameOfLife::with_seed(40, 40, 0.0, 11);
+    game.set_audio_affects_rules(false);
+    game.set_treble_gliders(Some(8));
+    
+    game.update(Some(&silence));
+    assert!(live_cells(&game).is_empty(), "no onset, no glider");
+    
+    game.update(Some(&hat));
+    let cells = live_cells(&game);
+    assert_eq!(cells.len(), 5);
+    assert!(cells.iter().all(|&(x, y)| x < 4 || y < 4 || x > 35 || y > 35), "launched from an edge: {:?}", cells);
+    
+    // Still cooling down, so the next hit adds nothing
+    game.update(Some(&hat));
+    assert_eq!(live_cells(&game).len(), 5);
+    
+    // And it heads inward, away from the edge it started at
+    let edge_distances = |game: &GameOfLife| {
+        let cells = live_cells(game);
+        let (x, y) = cells.iter().fold((0, 0), |(sx, sy), &(x, y)| (sx + x, sy + y));
+        let (x, y) = (x / cells.len(), y / cells.len());
+        [x, y, 39 - x, 39 - y]
+    };
+    let before = edge_distances(&game);
+    let edge = (0..4).min_by_key(|&i| before[i]).unwrap();
+    for _ in 0..12 {
+        game.update(Some(&silence));
+    }
+    assert!(edge_distances(&game)[edge] >= before[edge] + 2);
+}
+
+#[test]
+fn rule_decision_follows_the_zone_a_cell_is_in() {
+    // Six neighbors around (2, 2) inside a HighLife zone and around (7, 2) outside it
+    let six = |cx: usize| [(cx - 1, 1), (cx, 1), (cx + 1, 1), (cx - 1, 3), (cx, 3), (cx + 1, 3)];
+    let mut game = grid_with(10, 6, &[&six(2)[..], &six(7)[..]].concat());
+    game.set_edge_behavior(EdgeBehavior::Dead);
+    game.set_audio_affects_rules(false);
+    let highlife = Rect { x: 0, y: 0, width: 5, height: 6 };
+    game.set_rule_zones(vec![(highlife, rules::resolve_rule("highlife").unwrap())]);
+    
+    assert!(game.rule_decision(2, 2), "HighLife is born on six");
+    assert!(!game.rule_decision(7, 2), "Conway isn't");
+    
+    game.update(None);
+    assert!(game.is_cell_alive(2, 2));
+    assert!(!game.is_cell_alive(7, 2));
+}
+
+#[test]
+fn kernel_weights_neighbors_for_range_rules() {
+    // Von Neumann neighbors worth 5 each, so two of them reach a birth at 10
+    let mut game = grid_with(9, 9, &[(4, 3), (3, 4)]);
+    game.set_edge_behavior(EdgeBehavior::Dead);
+    game.set_audio_affects_rules(false);
+    game.set_kernel(Some(vec![vec![0, 5, 0], vec![5, 0, 5], vec![0, 5, 0]]));
+    let rule = rules::resolve_rule("B10-12/S").unwrap();
+    assert_eq!(rule.notation().as_deref(), Some("B10-12/S"));
+    game.set_ruleset(rule);
+    
+    game.update(None);
+    
+    let expected: BTreeSet<_> = [(3, 3), (4, 4)].into_iter().collect();
+    assert_eq!(live_cells(&game), expected);
+}
@@ -0,0 +1,97 @@
+use image::RgbImage;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use soundscape::audio::AudioAnalyzer;
+use soundscape::config::{ColorScheme, Config, InitialState, VisualizationMode};
+use soundscape::renderer::color::ColorPalette;
+use soundscape::renderer::draw;
+use soundscape::simulation::gol::GameOfLife;
+
+/// Set to rewrite the reference image after an intended rendering change
+const BLESS_VAR: &str = "BLESS_GOLDEN";
+
+/// Largest per-channel difference tolerated, for floating point drift across platforms
+const CHANNEL_TOLERANCE: u8 = 2;
+
+/// Generation that gets rendered, and the synthetic audio time between generations
+const GENERATIONS: u32 = 60;
+const STEP_SECS: f32 = 1.0 / 30.0;
+
+fn golden_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/golden_frame.png")
+}
+
+/// A small scene exercising the cells, the spectrum and dimming, with no
+/// randomness: a fixed starting pattern and rules the audio can't mutate
+fn golden_config() -> Config {
+    let mut config = Config::default();
+    config.window.width = 256;
+    config.window.height = 192;
+    config.simulation.width = 64;
+    config.simulation.height = 48;
+    config.simulation.initial_state = InitialState::Acorn;
+    config.simulation.audio_affects_rules = false;
+    config.visualization.cell_size = Some(4);
+    config.visualization.color_scheme = ColorScheme::Heat;
+    config.visualization.mode = VisualizationMode::Both;
+    config.visualization.dim_floor = 0.5;
+    config
+}
+
+/// Run the simulation against the synthetic signal and render the last generation as RGB
+fn render_golden() -> RgbImage {
+    let config = golden_config();
+    let mut sim = GameOfLife::from_config(&config.simulation, 0);
+    let analyzer = AudioAnalyzer::new(config.audio.clone());
+    let mut palette = ColorPalette::new(config.visualization.color_scheme.clone());
+    
+    let mut audio = None;
+    for generation in 0..GENERATIONS {
+        let frame = analyzer.generate_test_frame(generation as f32 * STEP_SECS);
+        sim.update(Some(&frame));
+        palette.update(Some(&frame), STEP_SECS);
+        audio = Some(frame);
+    }
+    
+    let (rgba, layout) = draw::render_frame(&config, &sim, &palette, audio.as_ref(), None);
+    let rgb = rgba.chunks_exact(4).flat_map(|pixel| pixel[..3].to_vec()).collect();
+    RgbImage::from_raw(layout.frame_size.0 as u32, layout.frame_size.1 as u32, rgb).unwrap()
+}
+
+fn write_png(path: &Path, image: &RgbImage) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    image.save(path).unwrap();
+}
+
+fn read_png(path: &Path) -> RgbImage {
+    image::open(path)
+        .unwrap_or_else(|e| panic!("Can't read {} ({}); run with {}=1 to create it", path.display(), e, BLESS_VAR))
+        .to_rgb8()
+}
+
+#[test]
+fn golden_frame() {
+    let frame = render_golden();
+    let path = golden_path();
+    if env::var_os(BLESS_VAR).is_some() {
+        write_png(&path, &frame);
+        return;
+    }
+    
+    let golden = read_png(&path);
+    assert_eq!(frame.dimensions(), golden.dimensions(), "frame size changed");
+    let mismatched = frame.pixels()
+        .zip(golden.pixels())
+        .filter(|(actual, expected)| actual.0.iter().zip(expected.0.iter()).any(|(a, e)| a.abs_diff(*e) > CHANNEL_TOLERANCE))
+        .count();
+    if mismatched > 0 {
+        let actual_path = env::temp_dir().join("golden_frame.actual.png");
+        write_png(&actual_path, &frame);
+        panic!(
+            "{} of {} pixels differ from {}; the new frame is at {} (run with {}=1 if the change is intended)",
+            mismatched, frame.width() * frame.height(), path.display(), actual_path.display(), BLESS_VAR
+        );
+    }
+}
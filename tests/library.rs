@@ -0,0 +1,738 @@
+use std::sync::{Arc, Mutex};
+
+use soundscape::audio::beat::BeatDetector;
+use soundscape::audio::resample::LinearResampler;
+use soundscape::audio::{AnalyzerCommand, AudioAnalyzer, AudioFrame};
+use soundscape::config::{AgeStep, AnalysisMode, AudioBand, AudioConfig, BackgroundMode, ChannelSource, ColorScheme, Config, SchemeParams};
+use soundscape::renderer::color::{Color, ColorPalette};
+use soundscape::renderer::{draw, hud, radial};
+use soundscape::simulation::gol::GameOfLife;
+
+#[test]
+fn game_of_life_is_reproducible_from_seed() {
+    let a = GameOfLife::with_seed(32, 24, 0.3, 7);
+    let b = GameOfLife::with_seed(32, 24, 0.3, 7);
+    
+    for y in 0..24 {
+        for x in 0..32 {
+            assert_eq!(a.is_cell_alive(x, y), b.is_cell_alive(x, y));
+        }
+    }
+}
+
+#[test]
+fn audio_mutations_are_reproducible_from_seed() {
+    // Loud treble drives mutations, the only source of live cells on an empty grid
+    let hiss = AudioFrame {
+        bass_energy: 0.0,
+        mid_energy: 0.0,
+        treble_energy: 1.0,
+        peak_frequency: 8000.0,
+        overall_energy: 0.5,
+        left: None,
+        right: None,
+        beat: false,
+        treble_onset: false,
+        estimated_bpm: None,
+        energy: Default::default(),
+        time: 0.0,
+        spectrum: None,
+        sequence: 0,
+    };
+    let run = |seed| {
+        let mut game = GameOfLife::with_seed(32, 24, 0.0, seed);
+        for _ in 0..10 {
+            game.update(Some(&hiss));
+        }
+        game.snapshot().cells
+    };
+    
+    let first = run(7);
+    assert!(first.iter().any(|&alive| alive), "mutations fired");
+    assert_eq!(run(7), first);
+    assert_ne!(run(8), first);
+}
+
+#[test]
+fn classic_palette_is_black_and_white() {
+    let palette = ColorPalette::new(ColorScheme::Classic);
+    
+    assert_eq!(palette.get_cell_color(1, 255), Color::white());
+    assert_eq!(palette.get_background_color(), Color::black());
+}
+
+#[test]
+fn beat_detector_flags_bass_spikes_once() {
+    let mut detector = BeatDetector::new(10.0);
+    
+    for _ in 0..10 {
+        assert!(!detector.detect(0.2));
+    }
+    assert!(detector.detect(1.0));
+    assert!(!detector.detect(1.0), "a sustained hit is a single beat");
+}
+
+#[test]
+fn config_rejects_empty_grid() {
+    let mut config = Config::default();
+    assert!(config.validate().is_ok());
+    
+    config.simulation.width = 0;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn noise_gate_silences_faint_hiss() {
+    let config = AudioConfig { gate_threshold: 0.5, ..Default::default() };
+    let mut analyzer = AudioAnalyzer::new(config);
+    let hiss: Vec<f32> = (0..4096).map(|i| if i % 2 == 0 { 0.001 } else { -0.001 }).collect();
+    
+    let frame = analyzer.process_audio(&hiss).unwrap().expect("a full window was supplied");
+    assert_eq!(frame.overall_energy, 0.0);
+}
+
+#[test]
+fn hud_text_is_clipped_to_the_frame() {
+    let mut frame = vec![0u8; 8 * 8 * 4];
+    assert_eq!(hud::text_width("AB", 1), 7);
+    
+    // Drawing past the right and bottom edges must not panic
+    hud::draw_text(&mut frame, 8, 6, 2, "AB", Color::white(), 2);
+    assert!(frame.chunks_exact(4).any(|pixel| pixel == [255, 255, 255, 255]));
+}
+
+#[test]
+fn history_plot_scrolls_and_fits_its_range() {
+    let mut history = hud::History::new(4);
+    for value in [5.0, 1.0, 2.0, 3.0, 4.0] {
+        history.push(value);
+    }
+    assert_eq!(history.range(), Some((1.0, 4.0)));
+    assert_eq!(history.latest(), Some(4.0));
+    
+    // Rising values from the bottom-left corner to the top-right one
+    let mut frame = vec![0u8; 4 * 4 * 4];
+    hud::draw_plot(&mut frame, 4, (0, 0), (4, 4), &history, Color::white());
+    let lit = |x: usize, y: usize| frame[(y * 4 + x) * 4] == 255;
+    assert!(lit(0, 3) && lit(3, 0));
+    assert!(!lit(0, 0) && !lit(3, 3));
+}
+
+#[test]
+fn beat_detector_estimates_tempo_from_regular_kicks() {
+    let mut detector = BeatDetector::new(10.0);
+    
+    // A kick every 5 frames at 10 frames per second is 120 BPM
+    for frame in 0..60 {
+        detector.detect(if frame % 5 == 0 { 1.0 } else { 0.1 });
+    }
+    let bpm = detector.bpm().expect("enough beats for a tempo");
+    assert!((bpm - 120.0).abs() < 0.01, "estimated {} BPM", bpm);
+}
+
+#[test]
+fn update_observer_runs_once_per_generation() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut game = GameOfLife::with_seed(16, 16, 0.3, 1);
+    let recorder = seen.clone();
+    game.set_on_update(Box::new(move |game| recorder.lock().unwrap().push(game.generation())));
+    
+    for _ in 0..3 {
+        game.update(None);
+    }
+    assert_eq!(*seen.lock().unwrap(), vec![1, 2, 3]);
+}
+
+#[test]
+fn grid_snapshot_restores_cells_and_generation() {
+    let mut game = GameOfLife::with_seed(16, 16, 0.3, 5);
+    game.update(None);
+    let snapshot = game.snapshot();
+    
+    for _ in 0..4 {
+        game.update(None);
+    }
+    game.restore(&snapshot).unwrap();
+    assert_eq!(game.snapshot(), snapshot);
+    assert_eq!(game.generation(), 1);
+    
+    let mut other = GameOfLife::with_seed(8, 8, 0.3, 5);
+    assert!(other.restore(&snapshot).is_err());
+}
+
+#[test]
+fn sustained_tone_reports_a_steady_peak_frequency() {
+    let mut analyzer = AudioAnalyzer::new(AudioConfig::default());
+    let rate = AudioConfig::default().sample_rate as f32;
+    // Deliberately between FFT bins
+    let tone: Vec<f32> = (0..rate as usize / 2)
+        .map(|i| (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / rate).sin())
+        .collect();
+    
+    let mut peaks = Vec::new();
+    for chunk in tone.chunks(1024) {
+        if let Some(frame) = analyzer.process_audio(chunk).unwrap() {
+            peaks.push(frame.peak_frequency);
+        }
+    }
+    assert!(peaks.len() > 4);
+    for peak in peaks {
+        assert!((peak - 1000.0).abs() < 5.0, "peak at {} Hz", peak);
+    }
+}
+
+#[test]
+fn radial_bars_put_bass_on_top_and_treble_below() {
+    let analyzer = AudioAnalyzer::new(AudioConfig::default());
+    let mut frame = analyzer.generate_test_frame(0.0);
+    frame.bass_energy = 1.0;
+    frame.mid_energy = 0.5;
+    frame.treble_energy = 0.0;
+    
+    let energies = radial::bar_energies(&frame, 8);
+    assert!(energies[0] > 0.8 && energies[7] > 0.8);
+    assert!(energies[3] < 0.2 && energies[4] < 0.2);
+    
+    let mut pixels = vec![0u8; 64 * 64 * 4];
+    radial::draw_radial(&mut pixels, 64, &energies, |_| Color::white());
+    assert!(pixels.chunks_exact(4).any(|pixel| pixel[0] == 255));
+    let center = (32 * 64 + 32) * 4;
+    assert_eq!(pixels[center], 0);
+}
+
+#[test]
+fn energy_stats_follow_a_steady_signal() {
+    let config = AudioConfig {
+        fft_size: 512,
+        energy_smoothing: 0.2,
+        ..Default::default()
+    };
+    let mut analyzer = AudioAnalyzer::new(config);
+    let tone: Vec<f32> = (0..44100).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+    
+    let mut last = None;
+    for chunk in tone.chunks(512) {
+        if let Some(frame) = analyzer.process_audio(chunk).unwrap() {
+            last = Some(frame);
+        }
+    }
+    let frame = last.expect("frames were produced");
+    // After a second the short average has caught up, the baseline hasn't
+    assert!((frame.energy.smoothed - frame.overall_energy).abs() < frame.overall_energy * 0.2);
+    assert!(frame.energy.baseline > 0.0 && frame.energy.baseline < frame.energy.smoothed);
+}
+
+#[test]
+fn translucent_cells_blend_over_the_background() {
+    let mut config = Config::default();
+    config.window.width = 40;
+    config.window.height = 40;
+    config.visualization.cell_size = Some(4);
+    config.visualization.color_scheme = ColorScheme::Classic;
+    config.visualization.cell_opacity = 0.5;
+    let mut sim = GameOfLife::with_seed(10, 10, 0.0, 1);
+    sim.set_cell(2, 3, true);
+    let palette = ColorPalette::new(ColorScheme::Classic);
+    
+    let (frame, layout) = draw::render_frame(&config, &sim, &palette, None, None);
+    let pixel = |x: usize, y: usize| {
+        let idx = (y * layout.frame_size.0 + x) * 4;
+        frame[idx..idx + 4].to_vec()
+    };
+    let expected = palette.get_background_color().blend(palette.get_cell_color(sim.cell_age(2, 3), 255), 0.5);
+    for channel in 0..3 {
+        assert!(pixel(2 * 4 + 1, 3 * 4 + 1)[channel].abs_diff(expected.to_rgba()[channel]) <= 1);
+    }
+    assert_eq!(pixel(2 * 4 + 1, 3 * 4 + 1)[3], 255);
+    assert_eq!(pixel(0, 0), palette.get_background_color().to_rgba().to_vec());
+}
+
+#[test]
+fn fft_size_can_be_switched_back_and_forth_while_running() {
+    let config = AudioConfig { fft_size: 512, ..Default::default() };
+    let mut analyzer = AudioAnalyzer::new(config);
+    let commands = analyzer.command_sender();
+    let rate = AudioConfig::default().sample_rate as f32;
+    let tone: Vec<f32> = (0..4096)
+        .map(|i| (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / rate).sin())
+        .collect();
+    
+    for fft_size in [1024, 512, 1024, 512] {
+        commands.send(AnalyzerCommand::SetFftSize(fft_size)).unwrap();
+        let mut frames = 0;
+        for chunk in tone.chunks(256) {
+            if let Some(frame) = analyzer.process_audio(chunk).unwrap() {
+                assert!((frame.peak_frequency - 1000.0).abs() < 20.0, "peak at {} Hz with {} bins", frame.peak_frequency, fft_size);
+                frames += 1;
+            }
+        }
+        assert_eq!(frames, tone.len() / fft_size);
+    }
+}
+
+#[test]
+fn paused_analyzer_emits_nothing_and_restarts_with_a_fresh_window() {
+    let config = AudioConfig { fft_size: 512, ..Default::default() };
+    let mut analyzer = AudioAnalyzer::new(config);
+    let commands = analyzer.command_sender();
+    let tone = vec![0.5; 384];
+    
+    // Leave part of a window buffered when the pause arrives
+    assert!(analyzer.process_audio(&tone).unwrap().is_none());
+    commands.send(AnalyzerCommand::SetPaused(true)).unwrap();
+    for _ in 0..4 {
+        assert!(analyzer.process_audio(&tone).unwrap().is_none());
+    }
+    assert!(analyzer.is_paused());
+    
+    // The samples from before the pause don't complete the first window after it
+    commands.send(AnalyzerCommand::SetPaused(false)).unwrap();
+    assert!(analyzer.process_audio(&tone).unwrap().is_none());
+    assert!(analyzer.process_audio(&tone).unwrap().is_some());
+}
+
+#[test]
+fn spectrum_capture_rides_on_the_next_frame_only() {
+    let config = AudioConfig { fft_size: 512, ..Default::default() };
+    let rate = config.sample_rate as f32;
+    let mut analyzer = AudioAnalyzer::new(config);
+    let tone: Vec<f32> = (0..512)
+        .map(|i| (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / rate).sin())
+        .collect();
+    
+    assert!(analyzer.process_audio(&tone).unwrap().unwrap().spectrum.is_none());
+    analyzer.command_sender().send(AnalyzerCommand::CaptureSpectrum).unwrap();
+    let frame = analyzer.process_audio(&tone).unwrap().unwrap();
+    let spectrum = frame.spectrum.expect("captured spectrum");
+    assert_eq!(spectrum.magnitudes.len(), 256);
+    let peak_bin = (0..256)
+        .max_by(|&a, &b| spectrum.magnitudes[a].total_cmp(&spectrum.magnitudes[b]))
+        .unwrap();
+    assert!((peak_bin as f32 * spectrum.bin_width - 1000.0).abs() <= spectrum.bin_width);
+    
+    let csv = spectrum.to_csv();
+    assert_eq!(csv.lines().next(), Some("bin,frequency_hz,magnitude"));
+    assert_eq!(csv.lines().count(), 257);
+    assert!(analyzer.process_audio(&tone).unwrap().unwrap().spectrum.is_none());
+}
+
+/// Mid band energy of a 1 kHz tone at `amplitude`, with or without dB scaling
+fn mid_energy_of_tone(amplitude: f32, log_amplitude: bool) -> f32 {
+    let config = AudioConfig { log_amplitude, ..Default::default() };
+    let rate = config.sample_rate as f32;
+    let mut analyzer = AudioAnalyzer::new(config);
+    let tone: Vec<f32> = (0..2048)
+        .map(|i| amplitude * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / rate).sin())
+        .collect();
+    analyzer.process_audio(&tone).unwrap().unwrap().mid_energy
+}
+
+#[test]
+fn log_amplitude_lifts_quiet_passages_into_range() {
+    // 40 dB apart: a hundredth of the loud level on a linear scale
+    let (quiet, loud) = (mid_energy_of_tone(0.01, false), mid_energy_of_tone(1.0, false));
+    assert!(quiet < loud * 0.02);
+    
+    let (quiet, loud) = (mid_energy_of_tone(0.01, true), mid_energy_of_tone(1.0, true));
+    assert!(loud <= 1.0 && loud > 0.5, "loud tone at {}", loud);
+    assert!(quiet > 0.05 && quiet < loud, "quiet tone at {}", quiet);
+    assert!(((loud - quiet) * 60.0 - 40.0).abs() < 1.0, "levels {} and {}", quiet, loud);
+}
+
+/// Left and right band energies of a 5.1 stream carrying a tone on one channel
+fn surround_balance(channel: usize) -> (f32, f32) {
+    let config = AudioConfig {
+        fft_size: 512,
+        analysis_mode: AnalysisMode::Stereo,
+        ..Default::default()
+    };
+    let rate = config.sample_rate as f32;
+    let mut analyzer = AudioAnalyzer::new(config);
+    let mut samples = vec![0.0; 512 * 6];
+    for (i, frame) in samples.chunks_exact_mut(6).enumerate() {
+        frame[channel] = (2.0 * std::f32::consts::PI * 500.0 * i as f32 / rate).sin();
+    }
+    
+    let frame = analyzer.process_interleaved(&samples, 6).unwrap().unwrap();
+    (frame.left.unwrap().overall_energy, frame.right.unwrap().overall_energy)
+}
+
+#[test]
+fn stereo_analysis_folds_surround_channels_into_their_side() {
+    // Rear left (channel 4) only reaches the left side
+    let (left, right) = surround_balance(4);
+    assert!(left > 0.0);
+    assert_eq!(right, 0.0);
+    
+    // Center (channel 2) is shared equally
+    let (left, right) = surround_balance(2);
+    assert!(left > 0.0);
+    assert!((left - right).abs() < left * 1e-3, "center split {} / {}", left, right);
+}
+
+#[test]
+fn beat_flash_holds_then_fades_out() {
+    let hold = 0.08;
+    assert_eq!(draw::beat_flash_level(0.0, hold), 1.0);
+    assert_eq!(draw::beat_flash_level(hold, hold), 1.0);
+    let fading = draw::beat_flash_level(hold + 0.05, hold);
+    assert!(fading > 0.0 && fading < 1.0, "level {} just after the hold", fading);
+    assert_eq!(draw::beat_flash_level(hold + 1.0, hold), 0.0);
+    
+    let mut frame = vec![100, 0, 255, 255];
+    draw::flash(&mut frame, 1.0);
+    assert!(frame[0] > 100 && frame[1] > 0);
+    assert_eq!(frame[2..], [255, 255]);
+}
+
+/// Treble energy of an ultrasonic tone at 88.2 kHz, and the peak frequency
+/// found for an audible one, with and without decimation
+fn analyze_high_rate(decimation: Option<u32>) -> (f32, f32) {
+    let config = AudioConfig {
+        fft_size: 512,
+        decimation,
+        ..Default::default()
+    };
+    let input_rate = 88200;
+    let tone = |hz: f32| -> Vec<f32> {
+        (0..8192).map(|i| (2.0 * std::f32::consts::PI * hz * i as f32 / input_rate as f32).sin()).collect()
+    };
+    
+    let mut analyzer = AudioAnalyzer::new(config.clone());
+    analyzer.set_input_sample_rate(input_rate);
+    let ultrasonic = analyzer.process_audio(&tone(30000.0)).unwrap().unwrap();
+    let mut analyzer = AudioAnalyzer::new(config);
+    analyzer.set_input_sample_rate(input_rate);
+    let audible = analyzer.process_audio(&tone(1000.0)).unwrap().unwrap();
+    (ultrasonic.treble_energy, audible.peak_frequency)
+}
+
+#[test]
+fn decimation_keeps_ultrasonics_out_of_the_bands() {
+    let (aliased, peak) = analyze_high_rate(None);
+    let (filtered, decimated_peak) = analyze_high_rate(Some(2));
+    assert!(filtered < aliased * 0.1, "treble {} decimated, {} without", filtered, aliased);
+    assert!((peak - 1000.0).abs() < 50.0 && (decimated_peak - 1000.0).abs() < 50.0, "peaks {} and {}", peak, decimated_peak);
+}
+
+#[test]
+fn tracks_at_48khz_land_in_the_right_bands() {
+    let input_rate = 48000;
+    let tone: Vec<f32> = (0..8192).map(|i| (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / input_rate as f32).sin()).collect();
+    
+    let mut analyzer = AudioAnalyzer::new(AudioConfig { fft_size: 512, ..Default::default() });
+    analyzer.set_input_sample_rate(input_rate);
+    let frame = analyzer.process_audio(&tone).unwrap().unwrap();
+    assert!((frame.peak_frequency - 1000.0).abs() < 50.0, "peak at {} Hz", frame.peak_frequency);
+    assert!(frame.mid_energy > frame.bass_energy && frame.mid_energy > frame.treble_energy,
+        "bass {}, mid {}, treble {}", frame.bass_energy, frame.mid_energy, frame.treble_energy);
+}
+
+#[test]
+fn resampler_output_does_not_depend_on_block_boundaries() {
+    let signal: Vec<f32> = (0..5000).map(|i| (i as f32 * 0.037).sin() + (i as f32 * 0.0051).cos()).collect();
+    
+    let mut whole = Vec::new();
+    LinearResampler::new(48000, 44100).process(&signal, &mut whole);
+    
+    let mut resampler = LinearResampler::new(48000, 44100);
+    let mut split = Vec::new();
+    let mut rest = &signal[..];
+    for size in [1, 7, 100, 2, 513, 3, 1024].iter().cycle() {
+        if rest.is_empty() {
+            break;
+        }
+        let (block, tail) = rest.split_at((*size).min(rest.len()));
+        resampler.process(block, &mut split);
+        rest = tail;
+    }
+    
+    assert_eq!(whole.len(), split.len());
+    for (a, b) in whole.iter().zip(&split) {
+        assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+    }
+}
+
+#[test]
+fn scrolling_wraps_rows_and_blends_fractional_offsets() {
+    let row = |values: [u8; 4]| -> Vec<u8> { values.iter().flat_map(|&v| [v, v, v, 255]).collect() };
+    let scrolled = |offset: f32| {
+        let mut frame = row([200, 0, 0, 0]);
+        draw::scroll(&mut frame, 4, offset);
+        frame
+    };
+    
+    assert_eq!(scrolled(1.0), row([0, 200, 0, 0]));
+    assert_eq!(scrolled(-1.0), row([0, 0, 0, 200]));
+    assert_eq!(scrolled(4.0), row([200, 0, 0, 0]));
+    assert_eq!(scrolled(0.5), row([100, 100, 0, 0]));
+}
+
+#[test]
+fn zoom_magnifies_about_the_center() {
+    let row = |values: [u8; 4]| -> Vec<u8> { values.iter().flat_map(|&v| [v, v, v, 255]).collect() };
+    let mut frame = row([0, 0, 200, 200]);
+    draw::zoom(&mut frame, 4, 1.0);
+    assert_eq!(frame, row([0, 0, 200, 200]));
+    
+    // Twice as large, the edge between the halves spreads over the middle pixels
+    draw::zoom(&mut frame, 4, 2.0);
+    assert_eq!(frame, row([0, 50, 150, 200]));
+}
+
+#[test]
+fn color_lookup_tables_match_the_computed_colors() {
+    for scheme in ColorScheme::ALL {
+        let mut palette = ColorPalette::new(scheme.clone());
+        let mut params = SchemeParams::default();
+        params.heat.curve = 0.5;
+        palette.set_scheme_params(params);
+        for age in 0..=255 {
+            assert_eq!(palette.get_cell_color(age, 255), palette.compute_cell_color(age, 255), "{:?} at age {}", scheme, age);
+        }
+    }
+}
+
+#[test]
+fn pulse_channels_follow_the_configured_bands() {
+    let analyzer = AudioAnalyzer::new(AudioConfig::default());
+    let mut frame = analyzer.generate_test_frame(0.0);
+    frame.bass_energy = 0.0;
+    frame.mid_energy = 0.0;
+    frame.treble_energy = 0.5;
+    
+    let mut params = SchemeParams::default();
+    params.pulse.channels = [
+        ChannelSource { band: AudioBand::Treble, gain: 2.0 },
+        ChannelSource { band: AudioBand::Treble, gain: 1.0 },
+        ChannelSource { band: AudioBand::Bass, gain: 1.0 },
+    ];
+    let mut palette = ColorPalette::new(ColorScheme::Pulse);
+    palette.set_scheme_params(params);
+    let color = palette.with_audio_frame(frame).get_cell_color(255, 255);
+    assert_eq!((color.r, color.g, color.b), (255, 127, 0));
+}
+
+#[test]
+fn long_exposure_of_a_glider_leaves_a_fading_diagonal_streak() {
+    let mut game = GameOfLife::new(30, 30, 0.0);
+    game.clear();
+    for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+        game.set_cell(x, y, true);
+    }
+    game.set_audio_affects_rules(false);
+    let analyzer = AudioAnalyzer::new(AudioConfig::default());
+    let frames = (0..).map(|i| analyzer.generate_test_frame(i as f32 / 30.0));
+    
+    let mut exposure = draw::LongExposure::new(30, 30, 0.9);
+    for snapshot in game.generations(frames).take(41) {
+        exposure.expose(&snapshot);
+    }
+    let levels = exposure.levels();
+    let level = |x: usize, y: usize| levels[y * 30 + x];
+    
+    // Ten cells down the diagonal, the glider is brightest where it is now
+    let brightest = levels.iter().position(|&level| level == 1.0).unwrap();
+    assert!(game.live_bounds().unwrap().contains(brightest % 30, brightest / 30));
+    assert!(level(1, 2) > 0.0 && level(1, 2) < level(6, 7) && level(6, 7) < level(12, 12));
+    assert_eq!(level(25, 3), 0.0);
+    assert_eq!(level(3, 25), 0.0);
+}
+
+#[test]
+fn a_full_latest_channel_keeps_the_newest_frames() {
+    let config = AudioConfig { fft_size: 256, ..Default::default() };
+    let (oldest_sender, oldest) = crossbeam_channel::bounded(2);
+    let (latest_sender, latest) = crossbeam_channel::bounded(2);
+    let mut analyzer = AudioAnalyzer::new(config);
+    analyzer.add_sender(oldest_sender);
+    analyzer.add_latest_sender(latest_sender, latest.clone());
+    
+    // Five frames into channels nobody reads
+    analyzer.process_audio(&vec![0.25; 256 * 5]).unwrap();
+    let times = |receiver: &crossbeam_channel::Receiver<AudioFrame>| -> Vec<f32> {
+        receiver.try_iter().map(|frame| frame.time).collect()
+    };
+    let first_two = times(&oldest);
+    let last_two = times(&latest);
+    assert_eq!(first_two.len(), 2);
+    assert_eq!(last_two.len(), 2);
+    assert!(last_two[0] > first_two[1], "latest {:?}, oldest {:?}", last_two, first_two);
+    assert!(last_two[0] < last_two[1]);
+}
+
+#[test]
+fn cell_cache_repaints_only_changed_cells() {
+    let mut config = Config::default();
+    config.window.width = 80;
+    config.window.height = 60;
+    config.visualization.cell_size = Some(4);
+    config.visualization.color_scheme = ColorScheme::Classic;
+    let palette = ColorPalette::new(ColorScheme::Classic);
+    let blinker = [(5, 6), (6, 6), (7, 6)];
+    let mut sim = GameOfLife::with_seed(20, 15, 0.0, 1);
+    for &(x, y) in &blinker {
+        sim.set_cell(x, y, true);
+    }
+    
+    let mut cache = draw::CellCache::new();
+    let (_, layout) = draw::render_frame(&config, &sim, &palette, None, None);
+    let mut frame = vec![0; layout.frame_size.0 * layout.frame_size.1 * 4];
+    cache.draw(&mut frame, &layout, &sim, &palette, 1.0);
+    assert_eq!(cache.pixels_written(), 80 * 60 + 3 * 16);
+    
+    // Nothing changed: nothing is painted
+    cache.draw(&mut frame, &layout, &sim, &palette, 1.0);
+    assert_eq!(cache.pixels_written(), 0);
+    
+    // The blinker flips: two cells die, two are born, and the result matches a full render
+    sim.update(None);
+    cache.draw(&mut frame, &layout, &sim, &palette, 1.0);
+    assert_eq!(cache.pixels_written(), 4 * 16);
+    assert_eq!(frame, draw::render_frame(&config, &sim, &palette, None, None).0);
+}
+
+#[test]
+fn fit_pillarboxes_grids_narrower_than_the_window() {
+    // A 4:3 grid in a 16:9 window keeps square cells, centered between background bars
+    let mut config = Config::default();
+    config.window.width = 64;
+    config.window.height = 36;
+    config.visualization.cell_size = None;
+    config.visualization.color_scheme = ColorScheme::Classic;
+    let palette = ColorPalette::new(ColorScheme::Classic);
+    let mut sim = GameOfLife::new(16, 12, 0.0);
+    for x in 0..16 {
+        sim.set_cell(x, 6, true);
+    }
+    
+    let (frame, layout) = draw::render_frame(&config, &sim, &palette, None, None);
+    assert_eq!(layout.frame_size, (64, 36));
+    assert_eq!(layout.cell_size, 3);
+    assert_eq!(layout.grid_offset, (8, 0));
+    
+    let pixel = |x: usize, y: usize| &frame[(y * 64 + x) * 4..(y * 64 + x) * 4 + 4];
+    let background = palette.get_background_color().to_rgba();
+    for x in [0, 7, 56, 63] {
+        assert_eq!(pixel(x, 18), background, "bar at column {}", x);
+    }
+    for x in [9, 33, 54] {
+        assert_ne!(pixel(x, 18), background, "cell at column {}", x);
+    }
+}
+
+#[test]
+fn stepped_scheme_changes_color_only_at_band_boundaries() {
+    let mut palette = ColorPalette::new(ColorScheme::Stepped);
+    let mut params = SchemeParams::default();
+    params.stepped.steps = vec![
+        AgeStep { age: 10, color: [0, 0, 255] },
+        AgeStep { age: 1, color: [255, 0, 0] },
+        AgeStep { age: 5, color: [0, 255, 0] },
+    ];
+    palette.set_scheme_params(params);
+    
+    let color = |age| palette.get_cell_color(age, 255);
+    assert_eq!(color(1), Color::new(255, 0, 0, 255));
+    assert_eq!(color(4), Color::new(255, 0, 0, 255));
+    assert_eq!(color(5), Color::new(0, 255, 0, 255));
+    assert_eq!(color(9), Color::new(0, 255, 0, 255));
+    assert_eq!(color(10), Color::new(0, 0, 255, 255));
+    assert_eq!(color(255), Color::new(0, 0, 255, 255));
+}
+
+#[test]
+fn frames_are_stamped_with_their_position_in_the_stream() {
+    let config = AudioConfig { fft_size: 512, ..Default::default() };
+    let rate = config.sample_rate as f32;
+    let mut analyzer = AudioAnalyzer::new(config);
+    let silence = vec![0.0; 512];
+    
+    let mut times = Vec::new();
+    for _ in 0..4 {
+        times.extend(analyzer.process_audio(&silence).unwrap().map(|frame| frame.time));
+    }
+    assert_eq!(times.len(), 4);
+    for (i, time) in times.iter().enumerate() {
+        assert!((time - (i + 1) as f32 * 512.0 / rate).abs() < 1e-4, "frame {} at {} s", i, time);
+    }
+    
+    // A gap in the input (dropped samples) is declared, and later frames follow it
+    analyzer.set_stream_time(10.0);
+    let frame = analyzer.process_audio(&silence).unwrap().unwrap();
+    assert!((frame.time - (10.0 + 512.0 / rate)).abs() < 1e-4);
+}
+
+#[test]
+fn background_mode_overrides_the_scheme_background() {
+    let mut palette = ColorPalette::new(ColorScheme::Classic);
+    
+    palette.set_background_mode(BackgroundMode::ChromaKey([0, 255, 0]));
+    assert_eq!(palette.get_background_color(), Color::new(0, 255, 0, 255));
+    palette.set_background_mode(BackgroundMode::Transparent);
+    assert_eq!(palette.get_background_color().a, 0);
+    palette.set_background_mode(BackgroundMode::Scheme);
+    assert_eq!(palette.get_background_color(), Color::black());
+}
+
+#[test]
+fn translucent_cells_over_a_transparent_background_keep_their_alpha() {
+    let mut pixel = [0, 0, 0, 0];
+    draw::blend_pixel(&mut pixel, Color::new(200, 100, 50, 128));
+    assert_eq!(pixel, [200, 100, 50, 128], "nothing below to mix with");
+    
+    draw::premultiply(&mut pixel);
+    assert_eq!(pixel, [100, 50, 25, 128]);
+    
+    // An opaque background is blended as before
+    let mut pixel = [0, 0, 0, 255];
+    draw::blend_pixel(&mut pixel, Color::new(200, 100, 50, 128));
+    assert_eq!(pixel, [100, 50, 25, 255]);
+}
+
+#[test]
+fn emitted_frames_are_numbered_in_order() {
+    let config = AudioConfig { fft_size: 512, ..Default::default() };
+    let mut analyzer = AudioAnalyzer::new(config);
+    let silence = vec![0.0; 512];
+    
+    let sequences: Vec<u64> = (0..3)
+        .filter_map(|_| analyzer.process_audio(&silence).unwrap())
+        .map(|frame| frame.sequence)
+        .collect();
+    assert_eq!(sequences, vec![1, 2, 3]);
+}
+
+#[test]
+fn stereo_test_signal_pans_between_the_channels() {
+    let config = AudioConfig { analysis_mode: AnalysisMode::Stereo, ..Default::default() };
+    let analyzer = AudioAnalyzer::new(config);
+    
+    let frame = analyzer.generate_test_frame(3.0);
+    let (left, right) = (frame.left.unwrap(), frame.right.unwrap());
+    assert!(right.overall_energy > left.overall_energy, "panned right at 3 s");
+    assert!(((left.bass_energy + right.bass_energy) / 2.0 - frame.bass_energy).abs() < 1e-6);
+    
+    let mono = AudioAnalyzer::new(AudioConfig::default()).generate_test_frame(3.0);
+    assert!(mono.left.is_none() && mono.right.is_none());
+}
+
+#[test]
+fn omitted_grid_size_tiles_the_window() {
+    let mut config = Config::default();
+    config.window.width = 1000;
+    config.window.height = 500;
+    config.visualization.cell_size = Some(10);
+    
+    config.fit_grid_to_window(true, false).unwrap();
+    assert_eq!((config.simulation.width, config.simulation.height), (100, 150), "only the omitted axis is derived");
+    config.fit_grid_to_window(true, true).unwrap();
+    assert_eq!((config.simulation.width, config.simulation.height), (100, 50));
+    
+    let layout = draw::Layout::new(&config, (config.simulation.width, config.simulation.height));
+    assert_eq!(layout.frame_size, (1000, 500));
+    assert_eq!(layout.grid_offset, (0, 0));
+    
+    config.window.width = 5;
+    assert!(config.fit_grid_to_window(true, true).is_err());
+}
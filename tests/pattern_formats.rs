@@ -0,0 +1,73 @@
+use std::collections::BTreeSet;
+
+use soundscape::simulation::gol::{GameOfLife, Rect};
+use soundscape::simulation::patterns::{self, parse_cells, parse_rle, GOSPER_GLIDER_GUN};
+
+fn live_cells(game: &GameOfLife) -> BTreeSet<(usize, usize)> {
+    (0..game.height())
+        .flat_map(|y| (0..game.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| game.is_cell_alive(x, y))
+        .collect()
+}
+
+#[test]
+fn exported_rle_round_trips_through_the_loader() {
+    let mut game = GameOfLife::new(48, 20, 0.0);
+    game.clear();
+    game.stamp_pattern(GOSPER_GLIDER_GUN, 3, 2);
+    game.stamp_pattern(&[(0, 0), (1, 0), (2, 0)], 10, 15);
+    
+    let rle = game.to_rle(None);
+    assert!(rle.starts_with("x = 48, y = 20, rule = B3/S23\n"));
+    assert!(rle.lines().all(|line| line.len() <= 70));
+    
+    let mut reloaded = GameOfLife::new(48, 20, 0.0);
+    reloaded.clear();
+    reloaded.stamp_pattern(&parse_rle(&rle).unwrap().cells, 0, 0);
+    assert_eq!(live_cells(&reloaded), live_cells(&game));
+}
+
+#[test]
+fn region_export_is_relative_to_the_region() {
+    let mut game = GameOfLife::new(20, 20, 0.0);
+    game.clear();
+    // Glider
+    game.stamp_pattern(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)], 7, 9);
+    
+    let bounds = game.live_bounds().unwrap();
+    assert_eq!(bounds, Rect { x: 7, y: 9, width: 3, height: 3 });
+    
+    let rle = game.to_rle(Some(bounds));
+    assert_eq!(rle, "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+}
+
+#[test]
+fn cells_and_rle_glider_guns_load_identically() {
+    let mut game = GameOfLife::new(40, 12, 0.0);
+    game.clear();
+    game.stamp_pattern(GOSPER_GLIDER_GUN, 0, 0);
+    
+    let from_rle = parse_rle(&game.to_rle(None)).unwrap();
+    let from_cells = parse_cells(&game.to_pattern(None).to_cells()).unwrap();
+    assert_eq!(from_cells.cells.iter().collect::<BTreeSet<_>>(), from_rle.cells.iter().collect());
+}
+
+#[test]
+fn cells_format_reads_names_and_comments() {
+    let pattern = parse_cells("!Name: Glider\n!A comment\n.O\n..O\nOOO\n").unwrap();
+    assert_eq!(pattern.name.as_deref(), Some("Glider"));
+    assert_eq!(pattern.cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    assert_eq!(pattern.to_cells(), "!Name: Glider\n.O\n..O\nOOO\n");
+}
+
+#[test]
+fn bundled_library_parses_with_names() {
+    let library = patterns::library();
+    let names: Vec<_> = library.iter().map(|pattern| pattern.name.as_deref().unwrap_or("")).collect();
+    assert_eq!(names, ["Glider", "Lightweight spaceship", "Gosper glider gun", "Pulsar"]);
+    
+    let gun: BTreeSet<_> = library[2].cells.iter().copied().collect();
+    assert_eq!(gun, GOSPER_GLIDER_GUN.iter().copied().collect());
+    assert_eq!(library[3].cells.len(), 48);
+    assert_eq!(library[3].size(), (13, 13));
+}